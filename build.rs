@@ -15,6 +15,8 @@ use serde::Deserialize;
 #[serde(rename_all = "lowercase")]
 enum HtmlDataNamespace {
     Html,
+    #[serde(rename = "mathml")]
+    MathMl,
     Svg,
 }
 
@@ -93,6 +95,7 @@ fn gen_attrs_rs(html_data: &HtmlData) -> String {
     pub struct ByNamespace {
         // Make pub so this struct can be statically created in code_gen/attrs.rs.
         pub html: Option<AttrMapEntry>,
+        pub mathml: Option<AttrMapEntry>,
         pub svg: Option<AttrMapEntry>,
     }
 
@@ -100,6 +103,7 @@ fn gen_attrs_rs(html_data: &HtmlData) -> String {
         fn get(&self, ns: Namespace) -> Option<&AttrMapEntry> {
             match ns {
                 Namespace::Html => self.html.as_ref(),
+                Namespace::MathMl => self.mathml.as_ref(),
                 Namespace::Svg => self.svg.as_ref(),
             }
         }
@@ -128,12 +132,17 @@ fn gen_attrs_rs(html_data: &HtmlData) -> String {
     for (attr_name, namespaces) in &html_data.attributes {
         write!(&mut code, r#"m.insert(b"{attr_name}", ByNamespace {{"#).unwrap();
         {
-            for ns in [HtmlDataNamespace::Html, HtmlDataNamespace::Svg] {
+            for ns in [
+                HtmlDataNamespace::Html,
+                HtmlDataNamespace::MathMl,
+                HtmlDataNamespace::Svg,
+            ] {
                 write!(
                     &mut code,
                     r#"{}:"#,
                     match ns {
                         HtmlDataNamespace::Html => "html",
+                        HtmlDataNamespace::MathMl => "mathml",
                         HtmlDataNamespace::Svg => "svg",
                     }
                 )
@@ -541,14 +550,44 @@ fn gen_entities_rs() -> String {
     )
 }
 
+// Event handler content attributes from the HTML, SVG, and other web platform specs. Other attributes
+// starting with `on`, such as those of custom elements, aren't code.
+fn gen_event_handlers_rs() -> String {
+    let names: Vec<String> = serde_json::from_slice(include_bytes!("event-handlers.json")).unwrap();
+    let mut code = r#"
+    use std::sync::LazyLock;
+    use rustc_hash::FxHashSet;
+
+    pub static EVENT_HANDLER_ATTRS: LazyLock<FxHashSet<&'static [u8]>> = LazyLock::new(|| {
+      let mut s = FxHashSet::<&'static [u8]>::default();
+  "#
+    .to_string();
+    for name in names {
+        write!(&mut code, r#"s.insert(b"{name}");"#).unwrap();
+    }
+    code.push_str("s\n});\n");
+    code
+}
+
 fn main() {
     let out_dir = var_os("OUT_DIR").unwrap();
     let out_dir = Path::new(&out_dir);
 
-    let html_data: HtmlData =
+    let mut html_data: HtmlData =
         serde_json::from_slice(include_bytes!("html-data_2023013104.0.0.json")).unwrap();
+    // The html-data package doesn't cover MathML, so its data is kept separately in the same format.
+    let mathml_data: HtmlData = serde_json::from_slice(include_bytes!("mathml-data.json")).unwrap();
+    html_data.tags.extend(mathml_data.tags);
+    for (attr_name, namespaces) in mathml_data.attributes {
+        html_data
+            .attributes
+            .entry(attr_name)
+            .or_default()
+            .extend(namespaces);
+    }
 
     std::fs::write(out_dir.join("attrs.rs"), gen_attrs_rs(&html_data)).unwrap();
     std::fs::write(out_dir.join("codepoints.rs"), gen_codepoints_rs()).unwrap();
     std::fs::write(out_dir.join("entities.rs"), gen_entities_rs()).unwrap();
+    std::fs::write(out_dir.join("event_handlers.rs"), gen_event_handlers_rs()).unwrap();
 }
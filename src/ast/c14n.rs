@@ -3,11 +3,13 @@ use std::{io::Write, sync::LazyLock};
 use aho_corasick::{AhoCorasickBuilder, AhoCorasickKind, MatchKind};
 
 use crate::{
-    ast::{ElementClosingTag, NodeData},
+    ast::{AttrVal, Attributes, ElementClosingTag, NodeData},
+    cfg::C14nCfg,
     pattern::Replacer,
+    whitespace::is_all_whitespace,
 };
 
-static TEXT_REPLACER: LazyLock<Replacer> = LazyLock::new(|| {
+pub static TEXT_REPLACER: LazyLock<Replacer> = LazyLock::new(|| {
     Replacer::new(
         AhoCorasickBuilder::new()
             .kind(Some(AhoCorasickKind::DFA))
@@ -68,20 +70,73 @@ static UNQUOTED_REPLACER: LazyLock<Replacer> = LazyLock::new(|| {
     )
 });
 
-pub fn c14n_serialise_ast<T: Write>(out: &mut T, node: &NodeData) -> std::io::Result<()> {
+// Writes an opening tag with the attributes in the order given.
+pub fn c14n_serialise_start_tag<'a, T: Write>(
+    out: &mut T,
+    cfg: &C14nCfg,
+    name: &[u8],
+    attributes: impl IntoIterator<Item = (&'a Vec<u8>, &'a AttrVal)>,
+    closing_tag: ElementClosingTag,
+) -> std::io::Result<()> {
+    out.write_all(b"<")?;
+    out.write_all(name)?;
+    for (name, value) in attributes {
+        out.write_all(b" ")?;
+        out.write_all(name)?;
+        if !value.value.is_empty() {
+            out.write_all(b"=")?;
+            let quote = if cfg.double_quote_attributes {
+                Some(b'"')
+            } else {
+                value.quote
+            };
+            match quote {
+                Some(b'"') => {
+                    out.write_all(b"\"")?;
+                    out.write_all(&DOUBLE_QUOTED_REPLACER.replace_all(&value.value))?;
+                    out.write_all(b"\"")?;
+                }
+                Some(b'\'') => {
+                    out.write_all(b"'")?;
+                    out.write_all(&SINGLE_QUOTED_REPLACER.replace_all(&value.value))?;
+                    out.write_all(b"'")?;
+                }
+                None => {
+                    out.write_all(&UNQUOTED_REPLACER.replace_all(&value.value))?;
+                }
+                _ => unreachable!(),
+            };
+        };
+    }
+    if closing_tag == ElementClosingTag::SelfClosing {
+        out.write_all(b" /")?;
+    };
+    out.write_all(b">")
+}
+
+pub fn c14n_serialise_ast<T: Write>(
+    out: &mut T,
+    cfg: &C14nCfg,
+    node: &NodeData,
+) -> std::io::Result<()> {
     match node {
         NodeData::Bang { code, .. } => {
             out.write_all(b"<!")?;
             out.write_all(code)?;
             out.write_all(b">")?;
         }
+        NodeData::Comment { .. } if cfg.remove_comments => {}
         NodeData::Comment { code, .. } => {
             out.write_all(b"<!--")?;
             out.write_all(code)?;
             out.write_all(b"-->")?;
         }
         NodeData::Doctype { .. } => {
-            out.write_all(b"<!DOCTYPE html")?;
+            if cfg.lowercase_doctype {
+                out.write_all(b"<!doctype html")?;
+            } else {
+                out.write_all(b"<!DOCTYPE html")?;
+            };
             out.write_all(b">")?;
         }
         NodeData::Element {
@@ -91,60 +146,218 @@ pub fn c14n_serialise_ast<T: Write>(out: &mut T, node: &NodeData) -> std::io::Re
             name,
             ..
         } => {
-            out.write_all(b"<")?;
-            out.write_all(name)?;
-            let mut attrs_sorted = attributes.iter().collect::<Vec<_>>();
-            attrs_sorted.sort_unstable_by(|a, b| a.0.cmp(b.0));
-            for (name, value) in &attrs_sorted {
-                out.write_all(b" ")?;
-                out.write_all(name)?;
-                if !value.value.is_empty() {
-                    out.write_all(b"=")?;
-                    match value.quote {
-                        Some(b'"') => {
-                            out.write_all(b"\"")?;
-                            out.write_all(&DOUBLE_QUOTED_REPLACER.replace_all(&value.value))?;
-                            out.write_all(b"\"")?;
-                        }
-                        Some(b'\'') => {
-                            out.write_all(b"'")?;
-                            out.write_all(&SINGLE_QUOTED_REPLACER.replace_all(&value.value))?;
-                            out.write_all(b"'")?;
-                        }
-                        None => {
-                            out.write_all(&UNQUOTED_REPLACER.replace_all(&value.value))?;
-                        }
-                        _ => unreachable!(),
-                    };
-                };
-            }
-            if closing_tag == &ElementClosingTag::SelfClosing {
-                out.write_all(b" /")?;
-            };
-            out.write_all(b">")?;
-            for c in children {
-                c14n_serialise_ast(out, c)?;
-            }
-            if closing_tag == &ElementClosingTag::Present {
-                out.write_all(b"</")?;
-                out.write_all(name)?;
-                out.write_all(b">")?;
-            };
+            c14n_serialise_element(out, cfg, name, attributes, *closing_tag, |out| {
+                c14n_serialise_nodes(out, cfg, children)
+            })?;
         }
         NodeData::Instruction { code, .. } => {
             out.write_all(b"<?")?;
             out.write_all(code)?;
             out.write_all(b"?>")?;
         }
-        NodeData::RcdataContent { typ: _, text } => {
+        NodeData::RcdataContent { text, .. } => {
             out.write_all(&TEXT_REPLACER.replace_all(text))?;
         }
         NodeData::ScriptOrStyleContent { code, .. } => {
             out.write_all(code)?;
         }
-        NodeData::Text { value } => {
+        NodeData::Text { value, .. } => {
             out.write_all(&TEXT_REPLACER.replace_all(value))?;
         }
+        NodeData::Verbatim { code, .. } => {
+            out.write_all(code)?;
+        }
     };
     Ok(())
 }
+
+// Writes an element with its attributes sorted by name, and its content written by `content`.
+fn c14n_serialise_element<T: Write>(
+    out: &mut T,
+    cfg: &C14nCfg,
+    name: &[u8],
+    attributes: &Attributes,
+    closing_tag: ElementClosingTag,
+    content: impl FnOnce(&mut T) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let mut attrs_sorted = attributes.iter().collect::<Vec<_>>();
+    attrs_sorted.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    c14n_serialise_start_tag(out, cfg, name, attrs_sorted, closing_tag)?;
+    content(out)?;
+    if closing_tag == ElementClosingTag::Present
+        || (closing_tag == ElementClosingTag::Omitted && cfg.insert_omitted_closing_tags)
+    {
+        out.write_all(b"</")?;
+        out.write_all(name)?;
+        out.write_all(b">")?;
+    };
+    Ok(())
+}
+
+pub fn is_element(node: &NodeData, tag_name: &[u8]) -> bool {
+    matches!(node, NodeData::Element { name, .. } if name.as_slice() == tag_name)
+}
+
+// Nodes that can come before the `html` element, or between `html` and `head` or `head` and `body`,
+// without implying the next element.
+fn is_outside_element(node: &NodeData) -> bool {
+    match node {
+        NodeData::Bang { .. }
+        | NodeData::Comment { .. }
+        | NodeData::Doctype { .. }
+        | NodeData::Instruction { .. } => true,
+        NodeData::Text { value, .. } => is_all_whitespace(value),
+        _ => false,
+    }
+}
+
+// Nodes that are put in an implied `head` element.
+fn is_head_content(node: &NodeData) -> bool {
+    match node {
+        NodeData::Element { name, .. } => matches!(
+            name.as_slice(),
+            b"base"
+                | b"basefont"
+                | b"bgsound"
+                | b"link"
+                | b"meta"
+                | b"noframes"
+                | b"script"
+                | b"style"
+                | b"template"
+                | b"title"
+        ),
+        NodeData::Text { value, .. } => is_all_whitespace(value),
+        _ => false,
+    }
+}
+
+// Where an `html`, `head`, or `body` element is among its siblings. If it isn't in the source,
+// browsers imply it around the nodes from `start` up to `end`.
+pub struct ImpliedElement {
+    pub present: Option<usize>,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Where the `html` element is among the top-level nodes of a document.
+pub fn implied_html(nodes: &[NodeData]) -> ImpliedElement {
+    let start = nodes.iter().take_while(|n| is_outside_element(n)).count();
+    ImpliedElement {
+        present: nodes[start..]
+            .iter()
+            .position(|n| is_element(n, b"html"))
+            .map(|i| start + i),
+        start,
+        end: nodes.len(),
+    }
+}
+
+// Where the `head` element is among the children of the `html` element. Browsers put leading
+// metadata in it.
+pub fn implied_head(nodes: &[NodeData]) -> ImpliedElement {
+    let start = nodes.iter().take_while(|n| is_outside_element(n)).count();
+    ImpliedElement {
+        present: nodes
+            .get(start)
+            .is_some_and(|n| is_element(n, b"head"))
+            .then_some(start),
+        start,
+        end: start
+            + nodes[start..]
+                .iter()
+                .take_while(|n| is_head_content(n))
+                .count(),
+    }
+}
+
+// Where the `body` element is among the children of the `html` element after the `head`. Browsers
+// put everything from the first node that can't be outside it in it.
+pub fn implied_body(nodes: &[NodeData]) -> ImpliedElement {
+    let start = nodes.iter().take_while(|n| is_outside_element(n)).count();
+    ImpliedElement {
+        present: nodes.iter().position(|n| is_element(n, b"body")),
+        start,
+        end: nodes.len(),
+    }
+}
+
+fn c14n_serialise_nodes<T: Write>(
+    out: &mut T,
+    cfg: &C14nCfg,
+    nodes: &[NodeData],
+) -> std::io::Result<()> {
+    for n in nodes {
+        c14n_serialise_ast(out, cfg, n)?;
+    }
+    Ok(())
+}
+
+// Writes the nodes up to and including the element `implied` describes, with its content written by
+// `content`, and returns the nodes after it. An implied element's closing tag is treated as omitted.
+fn c14n_serialise_implied<'n, T: Write>(
+    out: &mut T,
+    cfg: &C14nCfg,
+    nodes: &'n [NodeData],
+    name: &[u8],
+    implied: ImpliedElement,
+    content: impl FnOnce(&mut T, &'n [NodeData]) -> std::io::Result<()>,
+) -> std::io::Result<&'n [NodeData]> {
+    let Some(i) = implied.present else {
+        c14n_serialise_nodes(out, cfg, &nodes[..implied.start])?;
+        c14n_serialise_element(
+            out,
+            cfg,
+            name,
+            &Attributes::default(),
+            ElementClosingTag::Omitted,
+            |out| content(out, &nodes[implied.start..implied.end]),
+        )?;
+        return Ok(&nodes[implied.end..]);
+    };
+    c14n_serialise_nodes(out, cfg, &nodes[..i])?;
+    let NodeData::Element {
+        attributes,
+        children,
+        closing_tag,
+        name,
+        ..
+    } = &nodes[i]
+    else {
+        unreachable!();
+    };
+    c14n_serialise_element(out, cfg, name, attributes, *closing_tag, |out| {
+        content(out, children)
+    })?;
+    Ok(&nodes[i + 1..])
+}
+
+// Writes the children of the `html` element, adding `head` and `body` if they were implied.
+fn c14n_serialise_html_content<T: Write>(
+    out: &mut T,
+    cfg: &C14nCfg,
+    nodes: &[NodeData],
+) -> std::io::Result<()> {
+    let rest = c14n_serialise_implied(out, cfg, nodes, b"head", implied_head(nodes), |out, n| {
+        c14n_serialise_nodes(out, cfg, n)
+    })?;
+    let rest = c14n_serialise_implied(out, cfg, rest, b"body", implied_body(rest), |out, n| {
+        c14n_serialise_nodes(out, cfg, n)
+    })?;
+    c14n_serialise_nodes(out, cfg, rest)
+}
+
+// Writes the top-level nodes of a document.
+pub fn c14n_serialise_document<T: Write>(
+    out: &mut T,
+    cfg: &C14nCfg,
+    nodes: &[NodeData],
+) -> std::io::Result<()> {
+    if !cfg.insert_html_head_and_body {
+        return c14n_serialise_nodes(out, cfg, nodes);
+    };
+    let rest = c14n_serialise_implied(out, cfg, nodes, b"html", implied_html(nodes), |out, n| {
+        c14n_serialise_html_content(out, cfg, n)
+    })?;
+    c14n_serialise_nodes(out, cfg, rest)
+}
@@ -0,0 +1,268 @@
+use std::io::Write;
+
+use crate::{
+    ast::{
+        ElementClosingTag, NodeData,
+        c14n::{TEXT_REPLACER, c14n_serialise_ast, c14n_serialise_start_tag},
+    },
+    cfg::{C14nCfg, FormatCfg},
+    spec::tag::{
+        EMPTY_SLICE,
+        ns::Namespace,
+        whitespace::{WhitespaceMinification, get_whitespace_minification_for_tag},
+    },
+};
+
+// Code to write as is, or a point between two pieces of code where a line break is safe.
+enum Item {
+    Code(Vec<u8>),
+    Break {
+        // There was whitespace here, so at least a space must be written if the line isn't broken.
+        space: bool,
+        // Always break the line, such as around layout and content elements.
+        force: bool,
+        // Indentation level of the next line if the line is broken here.
+        depth: usize,
+    },
+}
+
+fn whitespace_of(node: &NodeData) -> Option<&'static WhitespaceMinification> {
+    match node {
+        NodeData::Element {
+            name, namespace, ..
+        } => Some(get_whitespace_minification_for_tag(*namespace, name, false)),
+        _ => None,
+    }
+}
+
+// Elements whose whitespace is trimmed, like `<div>` and `<p>`, go on their own lines.
+fn is_block(node: &NodeData) -> bool {
+    matches!(node, NodeData::Doctype { .. }) || whitespace_of(node).is_some_and(|ws| ws.trim)
+}
+
+fn is_text(node: Option<&NodeData>) -> bool {
+    matches!(
+        node,
+        Some(NodeData::Text { .. } | NodeData::Verbatim { .. })
+    )
+}
+
+// Adds a break between `prev` and `next`, which are None at the start and end of the content, if it's
+// safe to add whitespace there under the same assumptions the minifier makes when removing it.
+fn push_break(
+    items: &mut Vec<Item>,
+    ws: &WhitespaceMinification,
+    depth: usize,
+    space: bool,
+    prev: Option<&NodeData>,
+    next: Option<&NodeData>,
+    has_block: bool,
+) {
+    let (safe, force) = match (prev, next) {
+        (None, None) => return,
+        // Leading and trailing whitespace is trimmed.
+        (None, _) | (_, None) => (ws.trim, ws.destroy_whole && has_block),
+        // Whitespace between nodes that isn't next to text is removed in layout elements.
+        (Some(p), Some(n)) => (
+            ws.destroy_whole && !is_text(prev) && !is_text(next),
+            ws.destroy_whole && (is_block(p) || is_block(n)),
+        ),
+    };
+    if !safe && !space {
+        return;
+    };
+    // Whitespace after an element with an omitted closing tag is inside it, so its break and the
+    // parent's are the same.
+    if let Some(Item::Break {
+        space: s,
+        force: f,
+        depth: d,
+    }) = items.last_mut()
+    {
+        *s |= space;
+        *f |= force;
+        *d = depth;
+        return;
+    };
+    items.push(Item::Break {
+        space,
+        force,
+        depth,
+    });
+}
+
+fn push_content(
+    items: &mut Vec<Item>,
+    ws: &WhitespaceMinification,
+    nodes: &[NodeData],
+    depth: usize,
+    end_depth: usize,
+) {
+    let has_block = nodes.iter().any(is_block);
+    // Whether there has been whitespace since the last node or word.
+    let mut space = false;
+    let mut prev = None;
+    for n in nodes {
+        let NodeData::Text { value, .. } = n else {
+            push_break(items, ws, depth, space, prev, Some(n), has_block);
+            push_node(items, n, depth);
+            space = false;
+            prev = Some(n);
+            continue;
+        };
+        // Whitespace is collapsed, so any run of it can be a line break instead.
+        for (i, word) in value.split(u8::is_ascii_whitespace).enumerate() {
+            space |= i > 0;
+            if word.is_empty() {
+                continue;
+            };
+            push_break(items, ws, depth, space, prev, Some(n), has_block);
+            items.push(Item::Code(TEXT_REPLACER.replace_all(word)));
+            space = false;
+            prev = Some(n);
+        }
+    }
+    push_break(items, ws, end_depth, space, prev, None, has_block);
+}
+
+// Writes a node and its descendants as they are, with attributes in source order.
+fn write_as_is(out: &mut Vec<u8>, node: &NodeData) {
+    let NodeData::Element {
+        attributes,
+        children,
+        closing_tag,
+        name,
+        ..
+    } = node
+    else {
+        c14n_serialise_ast(out, &C14nCfg::default(), node).unwrap();
+        return;
+    };
+    c14n_serialise_start_tag(out, &C14nCfg::default(), name, attributes, *closing_tag).unwrap();
+    for c in children {
+        write_as_is(out, c);
+    }
+    if *closing_tag == ElementClosingTag::Present {
+        out.extend_from_slice(b"</");
+        out.extend_from_slice(name);
+        out.push(b'>');
+    };
+}
+
+fn push_node(items: &mut Vec<Item>, node: &NodeData, depth: usize) {
+    let NodeData::Element {
+        attributes,
+        children,
+        closing_tag,
+        name,
+        ..
+    } = node
+    else {
+        let mut code = Vec::new();
+        write_as_is(&mut code, node);
+        items.push(Item::Code(code));
+        return;
+    };
+    let ws = whitespace_of(node).unwrap();
+    // Whitespace-sensitive elements, and those with raw content like `<script>` and `<title>`, are
+    // written exactly as they are.
+    if !ws.collapse
+        || children.iter().any(|c| {
+            matches!(
+                c,
+                NodeData::RcdataContent { .. } | NodeData::ScriptOrStyleContent { .. }
+            )
+        })
+    {
+        let mut code = Vec::new();
+        write_as_is(&mut code, node);
+        items.push(Item::Code(code));
+        return;
+    };
+    let mut code = Vec::new();
+    c14n_serialise_start_tag(
+        &mut code,
+        &C14nCfg::default(),
+        name,
+        attributes,
+        *closing_tag,
+    )
+    .unwrap();
+    items.push(Item::Code(code));
+    // Inline elements like `<a>` don't add a level of indentation.
+    let inner_depth = if ws.trim { depth + 1 } else { depth };
+    push_content(items, ws, children, inner_depth, depth);
+    if *closing_tag == ElementClosingTag::Present {
+        items.push(Item::Code([b"</", name.as_slice(), b">"].concat()));
+    };
+}
+
+// Number of characters, which is how line width is measured.
+fn width(code: &[u8]) -> usize {
+    code.iter().filter(|&&c| c & 0xC0 != 0x80).count()
+}
+
+// Width of the code up to the next break or line break.
+fn width_until_break(items: &[Item]) -> usize {
+    let mut total = 0;
+    for item in items {
+        let Item::Code(code) = item else {
+            break;
+        };
+        match memchr::memchr(b'\n', code) {
+            Some(i) => return total + width(&code[..i]),
+            None => total += width(code),
+        };
+    }
+    total
+}
+
+// Writes nodes with each layout and content element on its own line, indented by nesting, and other
+// lines broken at whitespace when they get too long. Line breaks are only added where whitespace
+// already is, or where the minifier would remove it, so the document renders the same.
+pub fn format_nodes<T: Write>(
+    out: &mut T,
+    nodes: &[NodeData],
+    cfg: &FormatCfg,
+) -> std::io::Result<()> {
+    let mut items = Vec::new();
+    let ws = get_whitespace_minification_for_tag(Namespace::Html, EMPTY_SLICE, false);
+    push_content(&mut items, ws, nodes, 0, 0);
+    let mut buf = Vec::new();
+    let mut column = 0;
+    for (i, item) in items.iter().enumerate() {
+        match item {
+            Item::Code(code) => {
+                buf.extend_from_slice(code);
+                column = match memchr::memrchr(b'\n', code) {
+                    Some(nl) => width(&code[nl + 1..]),
+                    None => column + width(code),
+                };
+            }
+            // There's nothing to separate at the start.
+            Item::Break { .. } if buf.is_empty() => {}
+            &Item::Break {
+                space,
+                force,
+                depth,
+            } => {
+                let indent = depth * cfg.indent;
+                let fits = column + usize::from(space) + width_until_break(&items[i + 1..])
+                    <= cfg.line_width;
+                // Breaking a line that's no further than the indentation wouldn't make it shorter.
+                if force || (!fits && column > indent) {
+                    buf.push(b'\n');
+                    buf.resize(buf.len() + indent, b' ');
+                    column = indent;
+                } else if space {
+                    buf.push(b' ');
+                    column += 1;
+                };
+            }
+        };
+    }
+    if !buf.is_empty() && !buf.ends_with(b"\n") {
+        buf.push(b'\n');
+    };
+    out.write_all(&buf)
+}
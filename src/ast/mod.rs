@@ -1,14 +1,14 @@
-use std::{
-    fmt::{Debug, Formatter},
-    str::from_utf8,
-};
+use std::fmt::{Debug, Formatter};
 
-use rustc_hash::FxHashMap;
+use indexmap::IndexMap;
+use rustc_hash::FxBuildHasher;
 
-use crate::spec::tag::ns::Namespace;
+use crate::spec::tag::{ns::Namespace, void::VOID_TAGS};
 
 pub mod c14n;
+pub mod format;
 
+// How an element's end was written in the source.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum ElementClosingTag {
     Omitted,
@@ -17,6 +17,7 @@ pub enum ElementClosingTag {
     Void,
 }
 
+// Language of the raw content inside a `<script>` or `<style>` element.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum ScriptOrStyleLang {
@@ -24,15 +25,52 @@ pub enum ScriptOrStyleLang {
     Data,
     JS,
     JSModule,
+    // JSON data blocks such as `application/ld+json` and import maps.
+    JSON,
 }
 
+/// A range of bytes in the source, from `start` up to but not including `end`.
+///
+/// Nodes that weren't parsed from source, such as those created with [`Node::element`](crate::Node::element),
+/// have an empty span at the start.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub const fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+// A decoded attribute value.
 pub struct AttrVal {
+    // For diagnostics only, not used for equality or value. Where the name and the value, without any
+    // quotes, are in the source. A missing value has an empty span after the name.
+    pub name_span: Span,
+    pub span: Span,
     // For serialisation only, not used for equality or value.
     pub quote: Option<u8>,
     pub value: Vec<u8>,
 }
 
 impl AttrVal {
+    // Creates an unquoted attribute value; the minifier picks the best quoting when serialising.
+    pub fn new(value: impl Into<Vec<u8>>) -> AttrVal {
+        AttrVal {
+            name_span: Span::default(),
+            span: Span::default(),
+            quote: None,
+            value: value.into(),
+        }
+    }
+
     pub fn as_slice(&self) -> &[u8] {
         self.value.as_slice()
     }
@@ -40,7 +78,7 @@ impl AttrVal {
 
 impl Debug for AttrVal {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(from_utf8(&self.value).unwrap())
+        f.write_str(&String::from_utf8_lossy(&self.value))
     }
 }
 
@@ -52,75 +90,275 @@ impl PartialEq for AttrVal {
 
 impl Eq for AttrVal {}
 
+// An element's attributes by name, in the order they appear in the source.
+pub type Attributes = IndexMap<Vec<u8>, AttrVal, FxBuildHasher>;
+
+// The element whose text content a `NodeData::RcdataContent` node holds.
 #[derive(Eq, PartialEq, Debug, Copy, Clone)]
 pub enum RcdataContentType {
     Textarea,
     Title,
 }
 
-// Derive Eq for testing.
-#[derive(Eq, PartialEq)]
+// All text is stored decoded, i.e. entities have already been replaced with the characters they represent.
+// Every node has the span of the source it was parsed from, which isn't used for equality. The public API
+// over this is `dom::Node`.
 pub enum NodeData {
     Bang {
         code: Vec<u8>,
         // If the source unexpectedly ended before `>`, we can't add it, as otherwise output could be longer than source.
         ended: bool,
+        span: Span,
     },
     Comment {
         code: Vec<u8>,
         // If the source unexpectedly ended before `-->`, we can't add it, as otherwise output could be longer than source.
         ended: bool,
+        span: Span,
     },
     Doctype {
         // If the source unexpectedly ended before `>`, we can't add it, as otherwise output could be longer than source.
         ended: bool,
+        span: Span,
     },
     Element {
-        attributes: FxHashMap<Vec<u8>, AttrVal>,
+        attributes: Attributes,
         children: Vec<NodeData>,
         // If the source doesn't have a closing tag, then we can't add one, as otherwise output could be longer than source.
         closing_tag: ElementClosingTag,
+        // Between the end of the opening tag and the start of the closing tag, or where it would be.
+        content_span: Span,
         name: Vec<u8>,
+        name_span: Span,
         namespace: Namespace,
         // WARNING: This should only be set during minification, as minification can alter tree (e.g. remove text nodes completely).
         // If the next text or element sibling is an element, this will be set to its tag name.
         // Otherwise, this will be empty. It should be empty on creation.
         next_sibling_element_name: Vec<u8>,
+        span: Span,
     },
     Instruction {
         code: Vec<u8>,
         // If the source unexpectedly ended before `?>`, we can't add it, as otherwise output could be longer than source.
         ended: bool,
+        span: Span,
     },
     // <title> or <textarea> element contents.
     RcdataContent {
         typ: RcdataContentType,
         text: Vec<u8>,
+        span: Span,
     },
     // Entities should not be decoded in ScriptOrStyleContent.
     ScriptOrStyleContent {
         code: Vec<u8>,
         lang: ScriptOrStyleLang,
+        span: Span,
     },
     Text {
         value: Vec<u8>,
+        span: Span,
     },
+    // Source between `<!-- minify:off -->` and `<!-- minify:on -->` comments, which is written out
+    // exactly as is.
+    Verbatim {
+        code: Vec<u8>,
+        span: Span,
+    },
+}
+
+// Spans only say where a node came from, so they're ignored like those of `AttrVal`, which also lets
+// parse tests compare against nodes built by hand.
+impl PartialEq for NodeData {
+    fn eq(&self, other: &Self) -> bool {
+        use NodeData::*;
+        match (self, other) {
+            (
+                Bang {
+                    code: a, ended: x, ..
+                },
+                Bang {
+                    code: b, ended: y, ..
+                },
+            )
+            | (
+                Comment {
+                    code: a, ended: x, ..
+                },
+                Comment {
+                    code: b, ended: y, ..
+                },
+            )
+            | (
+                Instruction {
+                    code: a, ended: x, ..
+                },
+                Instruction {
+                    code: b, ended: y, ..
+                },
+            ) => a == b && x == y,
+            (Doctype { ended: a, .. }, Doctype { ended: b, .. }) => a == b,
+            (
+                Element {
+                    attributes: a1,
+                    children: c1,
+                    closing_tag: t1,
+                    name: n1,
+                    namespace: ns1,
+                    next_sibling_element_name: s1,
+                    ..
+                },
+                Element {
+                    attributes: a2,
+                    children: c2,
+                    closing_tag: t2,
+                    name: n2,
+                    namespace: ns2,
+                    next_sibling_element_name: s2,
+                    ..
+                },
+            ) => a1 == a2 && c1 == c2 && t1 == t2 && n1 == n2 && ns1 == ns2 && s1 == s2,
+            (
+                RcdataContent {
+                    typ: a, text: x, ..
+                },
+                RcdataContent {
+                    typ: b, text: y, ..
+                },
+            ) => a == b && x == y,
+            (
+                ScriptOrStyleContent {
+                    code: a, lang: x, ..
+                },
+                ScriptOrStyleContent {
+                    code: b, lang: y, ..
+                },
+            ) => a == b && x == y,
+            (Text { value: a, .. }, Text { value: b, .. })
+            | (Verbatim { code: a, .. }, Verbatim { code: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for NodeData {}
+
+impl NodeData {
+    // Creates an HTML element with no attributes or children.
+    pub fn new_element(name: impl Into<Vec<u8>>) -> NodeData {
+        let mut name = name.into();
+        name.make_ascii_lowercase();
+        NodeData::Element {
+            attributes: Attributes::default(),
+            children: Vec::new(),
+            closing_tag: if VOID_TAGS.contains(name.as_slice()) {
+                ElementClosingTag::Void
+            } else {
+                ElementClosingTag::Present
+            },
+            content_span: Span::default(),
+            name,
+            name_span: Span::default(),
+            namespace: Namespace::Html,
+            next_sibling_element_name: Vec::new(),
+            span: Span::default(),
+        }
+    }
+
+    // Creates a text node. The value should not have any entities encoded.
+    pub fn new_text(value: impl Into<Vec<u8>>) -> NodeData {
+        NodeData::Text {
+            value: value.into(),
+            span: Span::default(),
+        }
+    }
+
+    // Returns the range of the source this node was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            NodeData::Bang { span, .. }
+            | NodeData::Comment { span, .. }
+            | NodeData::Doctype { span, .. }
+            | NodeData::Element { span, .. }
+            | NodeData::Instruction { span, .. }
+            | NodeData::RcdataContent { span, .. }
+            | NodeData::ScriptOrStyleContent { span, .. }
+            | NodeData::Text { span, .. }
+            | NodeData::Verbatim { span, .. } => *span,
+        }
+    }
+
+    // Returns the tag name if this node is an element.
+    pub fn name(&self) -> Option<&[u8]> {
+        match self {
+            NodeData::Element { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    // Returns the value of an attribute if this node is an element that has it.
+    pub fn attr(&self, name: &[u8]) -> Option<&[u8]> {
+        match self {
+            NodeData::Element { attributes, .. } => attributes.get(name).map(AttrVal::as_slice),
+            _ => None,
+        }
+    }
+
+    // Sets an attribute, replacing any existing value in place or adding it after the others.
+    // Returns false if this node is not an element.
+    pub fn set_attr(&mut self, name: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> bool {
+        match self {
+            NodeData::Element { attributes, .. } => {
+                let mut name = name.into();
+                name.make_ascii_lowercase();
+                attributes.insert(name, AttrVal::new(value));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Removes an attribute, returning its previous value if there was one.
+    pub fn remove_attr(&mut self, name: &[u8]) -> Option<AttrVal> {
+        match self {
+            NodeData::Element { attributes, .. } => attributes.shift_remove(name),
+            _ => None,
+        }
+    }
+
+    // Returns the child nodes; empty for anything other than an element.
+    pub fn children(&self) -> &[NodeData] {
+        match self {
+            NodeData::Element { children, .. } => children,
+            _ => &[],
+        }
+    }
+
+    // Returns the child nodes if this node is an element.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<NodeData>> {
+        match self {
+            NodeData::Element { children, .. } => Some(children),
+            _ => None,
+        }
+    }
 }
 
 impl Debug for NodeData {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            NodeData::Bang { code, ended } => f
+            NodeData::Bang { code, ended, .. } => f
                 .debug_struct("Bang")
-                .field("code", &from_utf8(code).unwrap().to_string())
+                .field("code", &String::from_utf8_lossy(code))
                 .field("ended", ended)
                 .finish(),
-            NodeData::Comment { code, ended } => f
+            NodeData::Comment { code, ended, .. } => f
                 .debug_struct("Comment")
-                .field("code", &from_utf8(code).unwrap().to_string())
+                .field("code", &String::from_utf8_lossy(code))
                 .field("ended", ended)
                 .finish(),
-            NodeData::Doctype { ended } => f.debug_struct("Doctype").field("ended", ended).finish(),
+            NodeData::Doctype { ended, .. } => {
+                f.debug_struct("Doctype").field("ended", ended).finish()
+            }
             NodeData::Element {
                 attributes,
                 children,
@@ -128,12 +366,13 @@ impl Debug for NodeData {
                 name,
                 namespace,
                 next_sibling_element_name,
+                ..
             } => f
                 .debug_struct("Element")
                 .field("tag", &{
-                    let mut out = format!("{:?}:{}", namespace, from_utf8(name).unwrap());
+                    let mut out = format!("{:?}:{}", namespace, String::from_utf8_lossy(name));
                     for (n, v) in attributes {
-                        out.push_str(format!(" {}={:?}", from_utf8(n).unwrap(), v).as_str());
+                        out.push_str(format!(" {}={:?}", String::from_utf8_lossy(n), v).as_str());
                     }
                     out
                 })
@@ -141,25 +380,29 @@ impl Debug for NodeData {
                 .field("closing_tag", closing_tag)
                 .field(
                     "next_sibling_element_name",
-                    &from_utf8(next_sibling_element_name).unwrap().to_string(),
+                    &String::from_utf8_lossy(next_sibling_element_name),
                 )
                 .finish(),
-            NodeData::Instruction { code, ended } => f
+            NodeData::Instruction { code, ended, .. } => f
                 .debug_struct("Instruction")
-                .field("code", &from_utf8(code).unwrap().to_string())
+                .field("code", &String::from_utf8_lossy(code))
                 .field("ended", ended)
                 .finish(),
-            NodeData::RcdataContent { typ, text } => f
+            NodeData::RcdataContent { typ, text, .. } => f
                 .debug_struct("RcdataContent")
                 .field("typ", typ)
-                .field("text", &from_utf8(text).unwrap().to_string())
+                .field("text", &String::from_utf8_lossy(text))
                 .finish(),
-            NodeData::ScriptOrStyleContent { code, lang } => f
+            NodeData::ScriptOrStyleContent { code, lang, .. } => f
                 .debug_struct("ScriptOrStyleContent")
-                .field("code", &from_utf8(code).unwrap().to_string())
+                .field("code", &String::from_utf8_lossy(code))
                 .field("lang", lang)
                 .finish(),
-            NodeData::Text { value } => f.write_str(from_utf8(value).unwrap()),
+            NodeData::Text { value, .. } => f.write_str(&String::from_utf8_lossy(value)),
+            NodeData::Verbatim { code, .. } => f
+                .debug_struct("Verbatim")
+                .field("code", &String::from_utf8_lossy(code))
+                .finish(),
         }
     }
 }
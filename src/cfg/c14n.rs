@@ -0,0 +1,16 @@
+/// Settings for [`canonicalize`](crate::canonicalize). The default writes the document as it was
+/// parsed, except that attributes are sorted by name and the doctype is always `<!DOCTYPE html>`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct C14nCfg {
+    /// Write every attribute value in double quotes, instead of quoting it as it was in the source.
+    pub double_quote_attributes: bool,
+    /// Write the `html`, `head`, and `body` elements that were implied by the source, so documents
+    /// that only differ in whether they omit them serialise the same.
+    pub insert_html_head_and_body: bool,
+    /// Write the closing tags that were omitted in the source.
+    pub insert_omitted_closing_tags: bool,
+    /// Write the doctype as `<!doctype html>`.
+    pub lowercase_doctype: bool,
+    /// Drop comments.
+    pub remove_comments: bool,
+}
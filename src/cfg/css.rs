@@ -0,0 +1,91 @@
+/// Oldest browser versions that minified CSS must support, as `(major, minor)`. Browsers that are
+/// not set are not considered. When none are set, CSS is minified without lowering or prefixing.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CssTargets {
+    pub android: Option<(u8, u8)>,
+    pub chrome: Option<(u8, u8)>,
+    pub edge: Option<(u8, u8)>,
+    pub firefox: Option<(u8, u8)>,
+    pub ie: Option<(u8, u8)>,
+    pub ios_saf: Option<(u8, u8)>,
+    pub opera: Option<(u8, u8)>,
+    pub safari: Option<(u8, u8)>,
+    pub samsung: Option<(u8, u8)>,
+}
+
+impl CssTargets {
+    /// Parses queries of the form `<browser> <version>` or `<browser> >= <version>`, e.g.
+    /// `chrome 95`, `safari >= 14.1`, or `ios_saf 15`. These use browserslist's browser names, but
+    /// other browserslist queries such as `defaults` or `> 0.5%` aren't supported. If a browser
+    /// appears more than once, the oldest version is used.
+    pub fn from_queries<S: AsRef<str>>(
+        queries: impl IntoIterator<Item = S>,
+    ) -> Result<CssTargets, String> {
+        let mut targets = CssTargets::default();
+        for query in queries {
+            let query = query.as_ref();
+            let mut parts = query.split_whitespace();
+            let (Some(browser), Some(mut version)) = (parts.next(), parts.next()) else {
+                return Err(format!("invalid browser query `{query}`"));
+            };
+            if version == ">=" {
+                version = parts.next().unwrap_or_default();
+            };
+            if parts.next().is_some() {
+                return Err(format!("invalid browser query `{query}`"));
+            };
+            let version = parse_version(version)
+                .ok_or_else(|| format!("invalid version in browser query `{query}`"))?;
+            let slot = match browser.to_ascii_lowercase().as_str() {
+                "android" => &mut targets.android,
+                "chrome" => &mut targets.chrome,
+                "edge" => &mut targets.edge,
+                "firefox" | "ff" => &mut targets.firefox,
+                "ie" | "explorer" => &mut targets.ie,
+                "ios_saf" | "ios" => &mut targets.ios_saf,
+                "opera" => &mut targets.opera,
+                "safari" => &mut targets.safari,
+                "samsung" => &mut targets.samsung,
+                _ => return Err(format!("unknown browser in browser query `{query}`")),
+            };
+            *slot = Some(slot.map_or(version, |v| v.min(version)));
+        }
+        Ok(targets)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == CssTargets::default()
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u8, u8)> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    // Patch versions don't affect any CSS features.
+    Some((major, minor))
+}
+
+/// Settings for minifying CSS in `<style>` elements and `style` attributes. Only used with the
+/// `css` feature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CssOptions {
+    /// Drop invalid rules and declarations, instead of leaving the whole stylesheet or attribute
+    /// unminified. Each one dropped is reported as a warning.
+    pub error_recovery: bool,
+    /// Keep at-rules that aren't known, such as `@tailwind`, as they are. Otherwise they're removed.
+    /// Defaults to true.
+    pub preserve_unknown_at_rules: bool,
+    /// Browsers to lower syntax such as nesting for, and to add or remove vendor prefixes for.
+    pub targets: CssTargets,
+}
+
+impl Default for CssOptions {
+    fn default() -> CssOptions {
+        CssOptions {
+            error_recovery: false,
+            preserve_unknown_at_rules: true,
+            targets: CssTargets::default(),
+        }
+    }
+}
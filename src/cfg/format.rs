@@ -0,0 +1,18 @@
+/// Settings for [`format`](crate::format).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FormatCfg {
+    /// Number of spaces to indent each level of nesting by.
+    pub indent: usize,
+    /// Lines are broken where it's safe to keep them within this many characters, though tags,
+    /// words, and whitespace-sensitive content can make them longer.
+    pub line_width: usize,
+}
+
+impl Default for FormatCfg {
+    fn default() -> FormatCfg {
+        FormatCfg {
+            indent: 2,
+            line_width: 80,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+/// ECMAScript version that minified JavaScript may use syntax from.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum EsTarget {
+    Es5,
+    Es2015,
+    Es2016,
+    Es2017,
+    Es2018,
+    Es2019,
+    Es2020,
+    Es2021,
+    Es2022,
+    Es2023,
+    Es2024,
+    Es2025,
+    #[default]
+    EsNext,
+}
+
+/// Settings for minifying JavaScript in `<script>` elements, event handler attributes such as
+/// `onclick`, and `javascript:` URLs. Only used with the `js` feature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JsOptions {
+    /// Remove `console.*` calls.
+    pub drop_console: bool,
+    /// Remove `debugger` statements.
+    pub drop_debugger: bool,
+    /// Keep the `name` property of functions and classes the same.
+    pub keep_fn_names: bool,
+    /// Shorten the names of local variables and functions.
+    pub mangle: bool,
+    /// Also shorten names declared in the top-level scope. Other scripts on the page can no longer
+    /// refer to them, so this is off by default.
+    pub mangle_top_level: bool,
+    pub target: EsTarget,
+}
+
+impl Default for JsOptions {
+    fn default() -> JsOptions {
+        JsOptions {
+            drop_console: false,
+            drop_debugger: true,
+            keep_fn_names: false,
+            mangle: true,
+            mangle_top_level: false,
+            target: EsTarget::default(),
+        }
+    }
+}
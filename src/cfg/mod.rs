@@ -1,21 +1,88 @@
+use std::sync::Arc;
+
+pub use c14n::C14nCfg;
+pub use css::{CssOptions, CssTargets};
+pub use format::FormatCfg;
+pub use js::{EsTarget, JsOptions};
+
+mod c14n;
+mod css;
+mod format;
+mod js;
+
+/// Decides whether to keep a comment given its code. See [`Cfg::keep_comments_if`].
+pub type CommentPredicate = Arc<dyn Fn(&[u8]) -> bool + Send + Sync>;
+
+/// The order minified attributes are written in.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum AttrOrder {
+    /// Quoted values first and then unquoted ones, each sorted by name. This needs the fewest spaces
+    /// and compresses well.
+    #[default]
+    QuotedFirst,
+    /// Sorted by how often they're used in the document, most frequent first, with `class` names
+    /// ordered the same way. Repeating the same sequences throughout the document helps gzip and
+    /// Brotli compress the output, and is usually worth more than the few spaces saved by
+    /// [`QuotedFirst`](AttrOrder::QuotedFirst).
+    Frequency,
+    /// Sorted by name.
+    Name,
+    /// The order they appear in the source (or were added in, for a [`Document`](crate::Document)).
+    Source,
+}
+
 /// Configuration settings that can be adjusted and passed to a minification function to change the
 /// minification approach.
 #[derive(Clone, Default)]
 pub struct Cfg {
+    /// The order to write attributes in.
+    pub attr_order: AttrOrder,
+    /// Settings for CSS minification.
+    pub css: CssOptions,
+    /// Minify `<style>`, `<script>`, and `style` attribute code that isn't valid UTF-8 by replacing
+    /// invalid bytes with U+FFFD, instead of leaving that code as is.
+    pub decode_invalid_utf8_lossily: bool,
+    /// Settings for JavaScript minification.
+    pub js: JsOptions,
+    /// Do not omit `<body>`, `<colgroup>`, and `<tbody>` opening tags when they don't have
+    /// attributes.
+    pub keep_body_colgroup_and_tbody_opening_tags: bool,
     /// Do not omit closing tags when possible.
     pub keep_closing_tags: bool,
     /// Keep all comments.
     pub keep_comments: bool,
+    /// Keep comments for which this returns true. It's given the comment's code between `<!--` and
+    /// `-->`, so `|c| c.starts_with(b"!")` keeps `<!--! License -->`.
+    pub keep_comments_if: Option<CommentPredicate>,
+    /// Keep IE conditional comments, such as `<!--[if mso]>...<![endif]-->`.
+    pub keep_conditional_comments: bool,
     /// Do not omit `<html>` and `<head>` opening tags when they don't have attributes.
     pub keep_html_and_head_opening_tags: bool,
     /// Keep `type=text` attribute name and value on `<input>` elements.
     pub keep_input_type_text_attr: bool,
     /// Keep SSI comments.
     pub keep_ssi_comments: bool,
+    /// Minify the HTML inside kept conditional comments. It's left as is if it closes elements that
+    /// were opened outside the comment, or has other problems.
+    pub minify_conditional_comments: bool,
+    /// Shorten the numbers in SVG path data and `points` and `viewBox` attributes, and remove
+    /// separators between them where possible, e.g. `M 0.50,10.0 L 1,-1` becomes `M.5 10L1-1`.
+    pub minify_svg_path_data: bool,
     /// Remove all bangs.
     pub remove_bangs: bool,
+    /// Remove repeated names from `class` attributes, keeping the first occurrence of each.
+    pub remove_duplicate_classes: bool,
     /// Remove all processing instructions.
     pub remove_processing_instructions: bool,
+    /// Sort the names in `class` attributes. This makes repeated class lists identical for better
+    /// compression, and takes precedence over the class ordering of [`AttrOrder::Frequency`].
+    pub sort_classes: bool,
+    /// Opening and closing delimiters of template tags, such as `{{` and `}}`, to keep exactly as
+    /// they are. Template tags in text and attribute values are never collapsed, entity-encoded,
+    /// or re-quoted, attributes of elements with template tags between them keep their order, and
+    /// `<script>` and `<style>` code containing them isn't minified. See
+    /// [`Cfg::common_template_delimiters`].
+    pub template_delimiters: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 impl Cfg {
@@ -23,4 +90,15 @@ impl Cfg {
     pub fn new() -> Cfg {
         Cfg::default()
     }
+
+    /// Delimiters of common template languages for [`Cfg::template_delimiters`]: `{{ }}` (Jinja,
+    /// Django, Askama, Handlebars, Mustache, Liquid), `{% %}` and `{# #}` (Jinja, Django, Askama,
+    /// Liquid), and `<% %>` (ERB, EJS).
+    #[must_use]
+    pub fn common_template_delimiters() -> Vec<(Vec<u8>, Vec<u8>)> {
+        [("{{", "}}"), ("{%", "%}"), ("{#", "#}"), ("<%", "%>")]
+            .into_iter()
+            .map(|(open, close)| (open.into(), close.into()))
+            .collect()
+    }
 }
@@ -1,3 +1,4 @@
 pub mod attrs;
 pub mod codepoints;
 pub mod entities;
+pub mod event_handlers;
@@ -0,0 +1,194 @@
+use std::io::Write;
+
+use crate::{
+    AttrOrder, C14nCfg, Cfg, FormatCfg,
+    ast::{NodeData, c14n::c14n_serialise_document, format::format_nodes},
+    minify::{content::minify_content, order::order_by_frequency, output::Output},
+    parse::{Code, content::parse_content},
+    report::{Report, Warning},
+    spec::tag::{EMPTY_SLICE, ns::Namespace},
+};
+
+pub use self::node::{Attr, Node, NodeKind, NodeMut, NodeRef};
+
+mod node;
+#[cfg(test)]
+mod tests;
+
+/// A parsed HTML document that can be inspected and rewritten before it is serialised.
+///
+/// # Examples
+///
+/// ```
+/// use simple_minify_html::Document;
+///
+/// let mut doc = Document::parse(b"<p class=a>  Hello  </p>");
+/// doc.walk_mut(&mut |mut node| {
+///     if node.get().name() == Some(b"p".as_slice()) {
+///         node.set_attr("class", "b");
+///     }
+/// });
+/// assert_eq!(doc.minify(None), b"<p class=b>Hello".to_vec());
+/// ```
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Document {
+    children: Vec<NodeData>,
+    warnings: Vec<Warning>,
+}
+
+impl Document {
+    /// Parses UTF-8 HTML code. Like [`minify`](crate::minify), this never fails; malformed code is
+    /// interpreted the same way the minifier would.
+    #[must_use]
+    pub fn parse(src: &[u8]) -> Document {
+        Document::parse_with_cfg(src, &Cfg::default())
+    }
+
+    /// Parses UTF-8 HTML code like [`parse`](Document::parse), using the parsing-related settings
+    /// of `cfg`, such as [`Cfg::template_delimiters`].
+    #[must_use]
+    pub fn parse_with_cfg(src: &[u8], cfg: &Cfg) -> Document {
+        let mut code = Code::new_with_opts(src, &cfg.template_delimiters);
+        let parsed = parse_content(&mut code, Namespace::Html, EMPTY_SLICE, EMPTY_SLICE);
+        Document {
+            children: parsed.children,
+            warnings: code.warnings,
+        }
+    }
+
+    /// Creates a document from top-level nodes.
+    #[must_use]
+    pub fn from_nodes(children: Vec<Node>) -> Document {
+        Document {
+            children: children.into_iter().map(|n| n.0).collect(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// The top-level nodes of the document.
+    pub fn children(&self) -> impl DoubleEndedIterator<Item = NodeRef<'_>> + ExactSizeIterator {
+        self.children.iter().map(NodeRef)
+    }
+
+    /// Returns the top-level node at `index` if there is one.
+    pub fn child_mut(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.children.get_mut(index).map(NodeMut)
+    }
+
+    /// Adds a top-level node after the others.
+    pub fn push_child(&mut self, node: Node) {
+        self.children.push(node.0);
+    }
+
+    /// Inserts a top-level node at `index`, shifting the ones after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of top-level nodes.
+    pub fn insert_child(&mut self, index: usize, node: Node) {
+        self.children.insert(index, node.0);
+    }
+
+    /// Removes and returns the top-level node at `index` if there is one, shifting the ones after it.
+    pub fn remove_child(&mut self, index: usize) -> Option<Node> {
+        (index < self.children.len()).then(|| Node(self.children.remove(index)))
+    }
+
+    /// Problems found while parsing, such as dropped closing tags.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Takes the top-level nodes out of the document.
+    #[must_use]
+    pub fn into_nodes(self) -> Vec<Node> {
+        self.children.into_iter().map(Node).collect()
+    }
+
+    /// Visits every node in document order, parents before their children.
+    pub fn walk<F: FnMut(NodeRef<'_>)>(&self, f: &mut F) {
+        fn walk_nodes<F: FnMut(NodeRef<'_>)>(nodes: &[NodeData], f: &mut F) {
+            for n in nodes {
+                f(NodeRef(n));
+                walk_nodes(n.children(), f);
+            }
+        }
+        walk_nodes(&self.children, f);
+    }
+
+    /// Visits every node in document order, parents before their children. Children are visited
+    /// after the callback returns, so changes it makes to a node's children are reflected in the walk.
+    pub fn walk_mut<F: FnMut(NodeMut<'_>)>(&mut self, f: &mut F) {
+        fn walk_nodes<F: FnMut(NodeMut<'_>)>(nodes: &mut [NodeData], f: &mut F) {
+            for n in nodes {
+                f(NodeMut(n));
+                if let Some(children) = n.children_mut() {
+                    walk_nodes(children, f);
+                };
+            }
+        }
+        walk_nodes(&mut self.children, f);
+    }
+
+    /// Minifies the document. See [`minify`](crate::minify).
+    #[must_use]
+    pub fn minify(self, cfg: Option<Cfg>) -> Vec<u8> {
+        self.minify_with_report(cfg).output
+    }
+
+    /// Minifies the document, returning any parsing warnings along with those found while minifying.
+    /// See [`minify_with_report`](crate::minify_with_report).
+    #[must_use]
+    pub fn minify_with_report(self, cfg: Option<Cfg>) -> Report {
+        let mut output = Output::buffered(0);
+        let warnings = self.minify_into(cfg, &mut output);
+        Report {
+            // Writing to a buffer can't fail.
+            output: output.finish().unwrap(),
+            warnings,
+        }
+    }
+
+    /// Minifies the document, writing the output to `out` in chunks as it's produced, and returns the
+    /// warnings like [`minify_with_report`](Document::minify_with_report). See
+    /// [`minify_to_writer`](crate::minify_to_writer).
+    pub fn minify_to_writer<W: Write>(
+        self,
+        cfg: Option<Cfg>,
+        out: &mut W,
+    ) -> std::io::Result<Vec<Warning>> {
+        let mut output = Output::streaming(out);
+        let warnings = self.minify_into(cfg, &mut output);
+        output.finish().map(|_| warnings)
+    }
+
+    // Returns the parsing and minification warnings, sorted by offset.
+    pub(crate) fn minify_into(mut self, cfg: Option<Cfg>, output: &mut Output) -> Vec<Warning> {
+        let mut warnings = self.warnings;
+        let cfg = cfg.unwrap_or_default();
+        if cfg.attr_order == AttrOrder::Frequency {
+            order_by_frequency(&cfg, &mut self.children);
+        };
+        minify_content(
+            &cfg,
+            output,
+            &mut warnings,
+            Namespace::Html,
+            false,
+            EMPTY_SLICE,
+            self.children,
+        );
+        warnings.sort_by_key(|w| w.offset);
+        warnings
+    }
+
+    /// Serialises the document without minification. See [`canonicalize`](crate::canonicalize).
+    pub fn canonicalize<T: Write>(&self, out: &mut T, cfg: &C14nCfg) -> std::io::Result<()> {
+        c14n_serialise_document(out, cfg, &self.children)
+    }
+
+    /// Serialises the document indented and wrapped. See [`format`](crate::format).
+    pub fn format<T: Write>(&self, out: &mut T, cfg: &FormatCfg) -> std::io::Result<()> {
+        format_nodes(out, &self.children, cfg)
+    }
+}
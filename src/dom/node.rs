@@ -0,0 +1,262 @@
+use crate::{
+    ast::{AttrVal, NodeData, Span},
+    spec::tag::ns::Namespace,
+};
+
+/// What a node is.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NodeKind {
+    /// A bang such as `<!x>` that isn't a comment or doctype.
+    Bang,
+    Comment,
+    Doctype,
+    Element,
+    /// A processing instruction such as `<?xml ?>`.
+    Instruction,
+    /// The code inside a `<script>` or `<style>` element, which isn't decoded.
+    RawText,
+    /// Text, including the text inside a `<title>` or `<textarea>` element.
+    Text,
+    /// Source between `<!-- minify:off -->` and `<!-- minify:on -->` comments, which is written out
+    /// exactly as is.
+    Verbatim,
+}
+
+/// A node that isn't part of a [`Document`](crate::Document), such as a newly created one or one
+/// removed from a document. Add it to one with [`Document::push_child`](crate::Document::push_child)
+/// or [`NodeMut::push_child`].
+#[derive(Debug, Eq, PartialEq)]
+pub struct Node(pub(crate) NodeData);
+
+impl Node {
+    /// Creates an HTML element with no attributes or children. The name is lowercased.
+    pub fn element(name: impl Into<Vec<u8>>) -> Node {
+        Node(NodeData::new_element(name))
+    }
+
+    /// Creates a text node. The value should not have any entities encoded.
+    pub fn text(value: impl Into<Vec<u8>>) -> Node {
+        Node(NodeData::new_text(value))
+    }
+
+    pub fn get(&self) -> NodeRef<'_> {
+        NodeRef(&self.0)
+    }
+
+    pub fn get_mut(&mut self) -> NodeMut<'_> {
+        NodeMut(&mut self.0)
+    }
+}
+
+/// A reference to a node of a [`Document`] that can be inspected.
+///
+/// All text is decoded, i.e. entities have already been replaced with the characters they represent.
+/// Nodes that weren't parsed from source, such as those created with [`Node::element`], have empty
+/// spans at the start.
+///
+/// [`Document`]: crate::Document
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct NodeRef<'a>(pub(crate) &'a NodeData);
+
+impl<'a> NodeRef<'a> {
+    pub fn kind(&self) -> NodeKind {
+        match self.0 {
+            NodeData::Bang { .. } => NodeKind::Bang,
+            NodeData::Comment { .. } => NodeKind::Comment,
+            NodeData::Doctype { .. } => NodeKind::Doctype,
+            NodeData::Element { .. } => NodeKind::Element,
+            NodeData::Instruction { .. } => NodeKind::Instruction,
+            NodeData::ScriptOrStyleContent { .. } => NodeKind::RawText,
+            NodeData::RcdataContent { .. } | NodeData::Text { .. } => NodeKind::Text,
+            NodeData::Verbatim { .. } => NodeKind::Verbatim,
+        }
+    }
+
+    /// Returns the range of the source this node was parsed from.
+    pub fn span(&self) -> Span {
+        self.0.span()
+    }
+
+    /// Returns the lowercase tag name if this node is an element.
+    pub fn name(&self) -> Option<&'a [u8]> {
+        self.0.name()
+    }
+
+    /// Returns where the tag name is in the source if this node is an element.
+    pub fn name_span(&self) -> Option<Span> {
+        match self.0 {
+            NodeData::Element { name_span, .. } => Some(*name_span),
+            _ => None,
+        }
+    }
+
+    /// Returns where the content is in the source if this node is an element, between the end of
+    /// the opening tag and the start of the closing tag, or where it would be.
+    pub fn content_span(&self) -> Option<Span> {
+        match self.0 {
+            NodeData::Element { content_span, .. } => Some(*content_span),
+            _ => None,
+        }
+    }
+
+    /// Returns the namespace if this node is an element.
+    pub fn namespace(&self) -> Option<Namespace> {
+        match self.0 {
+            NodeData::Element { namespace, .. } => Some(*namespace),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an attribute if this node is an element that has it.
+    pub fn attr(&self, name: &[u8]) -> Option<&'a [u8]> {
+        self.0.attr(name)
+    }
+
+    /// Returns the attributes in the order they appear in the source; empty for anything other
+    /// than an element.
+    pub fn attrs(&self) -> impl Iterator<Item = Attr<'a>> + use<'a> {
+        let attributes = match self.0 {
+            NodeData::Element { attributes, .. } => Some(attributes),
+            _ => None,
+        };
+        attributes
+            .into_iter()
+            .flatten()
+            .map(|(name, val)| Attr { name, val })
+    }
+
+    /// Returns the child nodes; empty for anything other than an element.
+    pub fn children(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = NodeRef<'a>> + ExactSizeIterator + use<'a> {
+        self.0.children().iter().map(NodeRef)
+    }
+
+    /// Returns the text if this is a text node.
+    pub fn text(&self) -> Option<&'a [u8]> {
+        match self.0 {
+            NodeData::RcdataContent { text: value, .. } | NodeData::Text { value, .. } => {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the code as it is in the source if this is a bang, comment, instruction, raw text,
+    /// or verbatim node, without the delimiters around it such as `<!--` and `-->`.
+    pub fn code(&self) -> Option<&'a [u8]> {
+        match self.0 {
+            NodeData::Bang { code, .. }
+            | NodeData::Comment { code, .. }
+            | NodeData::Instruction { code, .. }
+            | NodeData::ScriptOrStyleContent { code, .. }
+            | NodeData::Verbatim { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+}
+
+/// An attribute of an element. See [`NodeRef::attrs`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Attr<'a> {
+    name: &'a [u8],
+    val: &'a AttrVal,
+}
+
+impl<'a> Attr<'a> {
+    /// Returns the lowercase name.
+    pub fn name(&self) -> &'a [u8] {
+        self.name
+    }
+
+    /// Returns the decoded value, which is empty if there is none.
+    pub fn value(&self) -> &'a [u8] {
+        self.val.as_slice()
+    }
+
+    /// Returns where the name is in the source.
+    pub fn name_span(&self) -> Span {
+        self.val.name_span
+    }
+
+    /// Returns where the value is in the source without any quotes, or an empty span after the name
+    /// if there is no value.
+    pub fn value_span(&self) -> Span {
+        self.val.span
+    }
+}
+
+/// A reference to a node of a [`Document`] that can be changed. Inspect it with
+/// [`get`](NodeMut::get).
+///
+/// [`Document`]: crate::Document
+#[derive(Debug, Eq, PartialEq)]
+pub struct NodeMut<'a>(pub(crate) &'a mut NodeData);
+
+impl NodeMut<'_> {
+    pub fn get(&self) -> NodeRef<'_> {
+        NodeRef(self.0)
+    }
+
+    /// Sets an attribute, replacing any existing value in place or adding it after the others.
+    /// Returns false if this node is not an element.
+    pub fn set_attr(&mut self, name: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> bool {
+        self.0.set_attr(name, value)
+    }
+
+    /// Removes an attribute, returning its previous value if there was one.
+    pub fn remove_attr(&mut self, name: &[u8]) -> Option<Vec<u8>> {
+        self.0.remove_attr(name).map(|v| v.value)
+    }
+
+    /// Sets the text of a text node. The value should not have any entities encoded. Returns false
+    /// if this is not a text node.
+    pub fn set_text(&mut self, value: impl Into<Vec<u8>>) -> bool {
+        match self.0 {
+            NodeData::RcdataContent { text, .. } | NodeData::Text { value: text, .. } => {
+                *text = value.into();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Replaces this node, returning the previous one.
+    pub fn replace(&mut self, node: Node) -> Node {
+        Node(std::mem::replace(self.0, node.0))
+    }
+
+    /// Returns a child node if this node is an element that has one at `index`.
+    pub fn child_mut(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.0.children_mut()?.get_mut(index).map(NodeMut)
+    }
+
+    /// Adds a child node after the others. Returns false if this node is not an element.
+    pub fn push_child(&mut self, node: Node) -> bool {
+        let len = self.0.children().len();
+        self.insert_child(len, node)
+    }
+
+    /// Inserts a child node at `index`, shifting the ones after it. Returns false if this node is
+    /// not an element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of children.
+    pub fn insert_child(&mut self, index: usize, node: Node) -> bool {
+        match self.0.children_mut() {
+            Some(children) => {
+                children.insert(index, node.0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes and returns the child node at `index` if this node is an element that has one,
+    /// shifting the ones after it.
+    pub fn remove_child(&mut self, index: usize) -> Option<Node> {
+        let children = self.0.children_mut()?;
+        (index < children.len()).then(|| Node(children.remove(index)))
+    }
+}
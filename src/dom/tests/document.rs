@@ -0,0 +1,253 @@
+use crate::{
+    C14nCfg, FormatCfg, Span,
+    dom::{Document, Node, NodeKind},
+};
+
+#[test]
+fn test_walk() {
+    let doc = Document::parse(b"<div><p>a<br>b</div><!-- c -->");
+    let mut names = Vec::new();
+    doc.walk(&mut |n| match n.kind() {
+        NodeKind::Element => names.push(String::from_utf8_lossy(n.name().unwrap()).to_string()),
+        NodeKind::Text => names.push(String::from_utf8_lossy(n.text().unwrap()).to_string()),
+        NodeKind::Comment => names.push("#comment".to_string()),
+        _ => {}
+    });
+    assert_eq!(names, vec!["div", "p", "a", "br", "b", "#comment"]);
+}
+
+#[test]
+fn test_mutate_and_minify() {
+    let mut doc = Document::parse(b"<ul>  <li class=x>1</li>  <li>2</li>  </ul>");
+    doc.walk_mut(&mut |mut n| {
+        if n.get().name() == Some(b"li".as_slice()) {
+            n.remove_attr(b"class");
+            n.set_attr("data-i", "");
+        };
+        if n.get().name() == Some(b"ul".as_slice()) {
+            let mut li = Node::element("LI");
+            li.get_mut().push_child(Node::text("3 < 4"));
+            n.push_child(li);
+        };
+    });
+    assert_eq!(
+        doc.minify(None),
+        b"<ul><li data-i>1<li data-i>2<li data-i>3 &lt; 4</ul>".to_vec()
+    );
+}
+
+#[test]
+fn test_minify_to_writer() {
+    let mut out = Vec::new();
+    let warnings = Document::parse(b"<p>a</div>")
+        .minify_to_writer(None, &mut out)
+        .unwrap();
+    assert_eq!(out, b"<p>a".to_vec());
+    assert_eq!(warnings[0].offset, 4);
+}
+
+#[test]
+fn test_debug_invalid_utf8() {
+    let doc = Document::parse(b"<p a=\xff>\xff<!--\xff-->");
+    assert!(format!("{doc:?}").contains('\u{FFFD}'));
+}
+
+#[test]
+fn test_new_void_element() {
+    let doc = Document::from_nodes(vec![Node::element("br")]);
+    assert_eq!(doc.minify(None), b"<br>".to_vec());
+}
+
+#[test]
+fn test_inspect() {
+    let doc =
+        Document::parse(b"<p id=a title='b c'>x &amp; y</p><!-- z --><script>1 &amp; 2</script>");
+    let children: Vec<_> = doc.children().collect();
+    let p = children[0];
+    assert_eq!(p.name(), Some(b"p".as_slice()));
+    assert_eq!(p.name_span(), Some(Span::new(1, 2)));
+    assert_eq!(p.content_span(), Some(Span::new(20, 29)));
+    assert_eq!(p.attr(b"title"), Some(b"b c".as_slice()));
+    let attrs: Vec<_> = p
+        .attrs()
+        .map(|a| (a.name(), a.value(), a.value_span()))
+        .collect();
+    assert_eq!(
+        attrs,
+        vec![
+            (b"id".as_slice(), b"a".as_slice(), Span::new(6, 7)),
+            (b"title".as_slice(), b"b c".as_slice(), Span::new(15, 18)),
+        ]
+    );
+    assert_eq!(
+        p.children().next().unwrap().text(),
+        Some(b"x & y".as_slice())
+    );
+    assert_eq!(children[1].kind(), NodeKind::Comment);
+    assert_eq!(children[1].code(), Some(b" z ".as_slice()));
+    let script = children[2].children().next().unwrap();
+    assert_eq!(script.kind(), NodeKind::RawText);
+    assert_eq!(script.code(), Some(b"1 &amp; 2".as_slice()));
+    assert_eq!(script.text(), None);
+}
+
+#[test]
+fn test_move_nodes() {
+    let mut doc = Document::parse(b"<div><p>a</p><p>b</p></div><span>c</span>");
+    let span = doc.remove_child(1).unwrap();
+    let mut div = doc.child_mut(0).unwrap();
+    let a = div.remove_child(0).unwrap();
+    assert!(div.insert_child(1, a));
+    assert!(div.push_child(span));
+    assert!(
+        div.child_mut(0)
+            .unwrap()
+            .child_mut(0)
+            .unwrap()
+            .set_text("B")
+    );
+    assert!(doc.remove_child(1).is_none());
+    let old = doc.child_mut(0).unwrap().replace(Node::text("d"));
+    doc.insert_child(0, old);
+    assert_eq!(
+        doc.minify(None),
+        b"<div><p>B<p>a</p><span>c</span></div>d".to_vec()
+    );
+}
+
+#[test]
+fn test_nodes_from_another_document() {
+    // Whether closing tags are omitted depends on where the nodes are now.
+    let nodes = Document::parse(b"<p>a</p><div>b</div>").into_nodes();
+    let mut reversed: Vec<_> = nodes.into_iter().rev().collect();
+    reversed.push(Node::element("p"));
+    assert_eq!(
+        Document::from_nodes(reversed).minify(None),
+        b"<div>b</div><p>a<p>".to_vec()
+    );
+}
+
+#[test]
+fn test_canonicalize() {
+    let doc = Document::parse(b"<p b=1 a='2'>x &amp; y");
+    let mut out = Vec::new();
+    doc.canonicalize(&mut out, &C14nCfg::default()).unwrap();
+    assert_eq!(out, b"<p a='2' b=1>x &amp; y".to_vec());
+}
+
+#[test]
+fn test_canonicalize_with_cfg() {
+    let cfg = C14nCfg {
+        double_quote_attributes: true,
+        insert_html_head_and_body: true,
+        insert_omitted_closing_tags: true,
+        lowercase_doctype: true,
+        remove_comments: true,
+    };
+    let canonicalize = |src: &[u8]| {
+        let mut out = Vec::new();
+        Document::parse(src).canonicalize(&mut out, &cfg).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let expected = "<!doctype html><html lang=\"en\"><head><meta charset=\"utf-8\"></head><body><ul><li>a</li><li>b</li></ul></body></html>";
+    // Documents that differ only in what they omit serialise the same.
+    assert_eq!(
+        canonicalize(
+            b"<!DOCTYPE html><html lang=en><meta charset=utf-8><!-- x --><ul><li>a<li>b</ul>"
+        ),
+        expected,
+    );
+    assert_eq!(
+        canonicalize(b"<!doctype html><html lang='en'><head><meta charset=\"utf-8\"></head><body><ul><li>a</li><li>b</li></ul></body></html>"),
+        expected,
+    );
+    assert_eq!(
+        canonicalize(b"<p>x"),
+        "<html><head></head><body><p>x</p></body></html>",
+    );
+}
+
+#[test]
+fn test_format() {
+    let doc = Document::parse(
+        b"<!doctype html><div class=a><p>Hello <b>bold</b> world, this paragraph is long enough to wrap.</p><pre> keep\n  this</pre><span>a</span><span>b</span></div>",
+    );
+    let mut out = Vec::new();
+    doc.format(
+        &mut out,
+        &FormatCfg {
+            indent: 2,
+            line_width: 40,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&out).unwrap(),
+        "<!DOCTYPE html>\n<div class=a>\n  <p>Hello <b>bold</b> world, this\n    paragraph is long enough to wrap.\n  </p>\n  <pre> keep\n  this</pre><span>a</span><span>b</span>\n</div>\n",
+    );
+}
+
+fn format(src: &[u8], line_width: usize) -> String {
+    let mut out = Vec::new();
+    Document::parse(src)
+        .format(
+            &mut out,
+            &FormatCfg {
+                indent: 2,
+                line_width,
+            },
+        )
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_format_as_is() {
+    // Attributes keep their source order inside whitespace-sensitive and raw elements too.
+    assert_eq!(
+        format(
+            b"<pre b=1 a=2> x <i d=1 c=2>y</i></pre><textarea z=1 y=2>a</textarea>",
+            80
+        ),
+        "<pre b=1 a=2> x <i d=1 c=2>y</i></pre><textarea z=1 y=2>a</textarea>\n",
+    );
+}
+
+#[test]
+fn test_format_inline_without_whitespace() {
+    // There's no whitespace between `foo`, `<b>` and `baz`, so they can't be broken apart.
+    assert_eq!(
+        format(
+            b"<p>foo<b>bar</b>baz and some more text to go past the line width</p>",
+            10
+        ),
+        "<p>\n  foo<b>bar</b>baz\n  and some\n  more\n  text to\n  go past\n  the line\n  width\n</p>\n",
+    );
+}
+
+#[test]
+fn test_format_omitted_closing_tags() {
+    assert_eq!(
+        format(b"<ul><li>one<li>two</ul><p>a<p>b", 80),
+        "<ul>\n  <li>one\n  <li>two\n</ul>\n<p>a\n<p>b\n",
+    );
+}
+
+#[test]
+fn test_format_comments() {
+    assert_eq!(
+        format(b"<div><!-- a comment --><p>text</p><!-- b --></div>", 80),
+        "<div>\n  <!-- a comment -->\n  <p>text</p>\n  <!-- b -->\n</div>\n",
+    );
+}
+
+#[test]
+fn test_format_nested_inline() {
+    assert_eq!(
+        format(
+            b"<p>some <b>bold and <i>italic text</i> that</b> wraps</p>",
+            12
+        ),
+        "<p>some\n  <b>bold\n  and\n  <i>italic\n  text</i>\n  that</b>\n  wraps</p>\n",
+    );
+}
@@ -0,0 +1,410 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    ast::{
+        AttrVal, NodeData,
+        c14n::{
+            ImpliedElement, c14n_serialise_ast, c14n_serialise_start_tag, implied_body,
+            implied_head, implied_html, is_element,
+        },
+    },
+    cfg::C14nCfg,
+    code_gen::attrs::ATTRS,
+    minify::attr::{is_redundant_attr, normalise_attr_case, normalise_attr_whitespace},
+    parse::{Code, content::parse_content},
+    spec::tag::{EMPTY_SLICE, ns::Namespace, whitespace::get_whitespace_minification_for_tag},
+    whitespace::{collapse_whitespace, left_trim, right_trim},
+};
+
+/// Where two documents compared with [`semantically_equal`](crate::semantically_equal) first differ.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Difference {
+    /// Path from the root to the differing node, such as `/html[1]/body[1]/ul[1]/li[2]`, or to the
+    /// differing attribute, such as `/html[1]/body[1]/p[1]/@class`. Each node is numbered among its
+    /// siblings of the same name, and text is named `text()`.
+    pub path: String,
+    /// The node or attribute in the first document after normalisation, or empty if it has none there.
+    pub left: String,
+    /// The node or attribute in the second document after normalisation, or empty if it has none there.
+    pub right: String,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let or_nothing = |s: &str| {
+            if s.is_empty() {
+                "nothing".to_string()
+            } else {
+                format!("`{s}`")
+            }
+        };
+        write!(
+            f,
+            "documents differ at {}: {} != {}",
+            self.path,
+            or_nothing(&self.left),
+            or_nothing(&self.right)
+        )
+    }
+}
+
+impl Error for Difference {}
+
+// Puts the content of the element `implied` describes through `content`, after moving it into a new
+// element if it's implied. Leaves `nodes` ending with the element, and returns the nodes after it.
+fn insert_implied(
+    nodes: &mut Vec<NodeData>,
+    name: &str,
+    implied: ImpliedElement,
+    content: impl FnOnce(Vec<NodeData>) -> Vec<NodeData>,
+) -> Vec<NodeData> {
+    match implied.present {
+        Some(i) => {
+            let rest = nodes.split_off(i + 1);
+            let children = nodes[i].children_mut().unwrap();
+            *children = content(std::mem::take(children));
+            rest
+        }
+        None => {
+            let rest = nodes.split_off(implied.end);
+            let children = nodes.split_off(implied.start);
+            nodes.push(new_element_with_children(name, content(children)));
+            rest
+        }
+    }
+}
+
+// Moves the nodes browsers would put in implied `html`, `head`, and `body` elements into them, the
+// same way `canonicalize` does with `C14nCfg::insert_html_head_and_body`.
+fn insert_html_head_and_body(mut nodes: Vec<NodeData>) -> Vec<NodeData> {
+    let implied = implied_html(&nodes);
+    let rest = insert_implied(&mut nodes, "html", implied, insert_head_and_body);
+    nodes.extend(rest);
+    nodes
+}
+
+fn insert_head_and_body(mut nodes: Vec<NodeData>) -> Vec<NodeData> {
+    let implied = implied_head(&nodes);
+    let mut rest = insert_implied(&mut nodes, "head", implied, |n| n);
+    let implied = implied_body(&rest);
+    let after = insert_implied(&mut rest, "body", implied, |n| n);
+    nodes.extend(rest);
+    nodes.extend(after);
+    nodes
+}
+
+fn new_element_with_children(name: &str, children: Vec<NodeData>) -> NodeData {
+    let mut elem = NodeData::new_element(name);
+    *elem.children_mut().unwrap() = children;
+    elem
+}
+
+// Wraps runs of `<tr>` and `<col>` children of a `<table>` in the `<tbody>` and `<colgroup>` elements
+// browsers imply for them.
+fn insert_tbody_and_colgroup(nodes: Vec<NodeData>) -> Vec<NodeData> {
+    let mut out = Vec::<NodeData>::with_capacity(nodes.len());
+    // The implied element that the previous node was put in, if any.
+    let mut implied = None;
+    for n in nodes {
+        let wrapper = if is_element(&n, b"tr") {
+            Some("tbody")
+        } else if is_element(&n, b"col") {
+            Some("colgroup")
+        } else {
+            None
+        };
+        match wrapper {
+            Some(w) if implied == Some(w) => {
+                out.last_mut().unwrap().children_mut().unwrap().push(n);
+            }
+            Some(w) => out.push(new_element_with_children(w, vec![n])),
+            None => out.push(n),
+        };
+        implied = wrapper;
+    }
+    out
+}
+
+// Returns the value of an attribute as the minifier would compare it, or None if it's redundant.
+fn normalise_attr(
+    ns: Namespace,
+    tag: &[u8],
+    is_meta_viewport: bool,
+    name: &[u8],
+    mut value: Vec<u8>,
+) -> Option<Vec<u8>> {
+    let attr_cfg = ATTRS.get(ns, tag, name);
+    normalise_attr_whitespace(attr_cfg, is_meta_viewport, &mut value);
+    normalise_attr_case(attr_cfg, &mut value);
+    if is_redundant_attr(attr_cfg, tag, name, &value) {
+        return None;
+    };
+    if attr_cfg.is_some_and(|a| a.boolean) {
+        value.clear();
+    };
+    // The order and repetition of class names don't matter.
+    if name == b"class" {
+        let mut classes = value
+            .split(u8::is_ascii_whitespace)
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<_>>();
+        classes.sort_unstable();
+        classes.dedup();
+        value = classes.join(b" ".as_slice());
+    };
+    Some(value)
+}
+
+// Removes what doesn't affect how the content renders, following the same whitespace rules as
+// `minify_content`, so that content differs after normalisation only if it renders differently.
+fn normalise(
+    ns: Namespace,
+    descendant_of_pre: bool,
+    parent: &[u8],
+    nodes: Vec<NodeData>,
+) -> Vec<NodeData> {
+    let ws = get_whitespace_minification_for_tag(ns, parent, descendant_of_pre);
+    // Comments, bangs, and processing instructions aren't rendered, so the text around them is
+    // joined.
+    let mut nodes = nodes.into_iter().fold(Vec::new(), |mut nodes, n| {
+        match n {
+            NodeData::Bang { .. } | NodeData::Comment { .. } | NodeData::Instruction { .. } => {}
+            NodeData::Text { value, .. } if matches!(nodes.last(), Some(NodeData::Text { .. })) => {
+                let Some(NodeData::Text { value: prev, .. }) = nodes.last_mut() else {
+                    unreachable!();
+                };
+                prev.extend_from_slice(&value);
+            }
+            n => nodes.push(n),
+        };
+        nodes
+    });
+    let is_text_or_element = |n: &NodeData| {
+        matches!(
+            n,
+            NodeData::Element { .. } | NodeData::Text { .. } | NodeData::Verbatim { .. }
+        )
+    };
+    let first = nodes.iter().position(is_text_or_element);
+    let last = nodes.iter().rposition(is_text_or_element);
+    for (i, n) in nodes.iter_mut().enumerate() {
+        match n {
+            NodeData::Element {
+                attributes,
+                children,
+                name,
+                namespace,
+                ..
+            } => {
+                let is_meta_viewport = name.as_slice() == b"meta"
+                    && attributes
+                        .get(b"name".as_slice())
+                        .is_some_and(|a| a.value.eq_ignore_ascii_case(b"viewport"));
+                attributes.retain(|attr, value| {
+                    match normalise_attr(
+                        *namespace,
+                        name,
+                        is_meta_viewport,
+                        attr,
+                        std::mem::take(&mut value.value),
+                    ) {
+                        Some(v) => {
+                            value.value = v;
+                            true
+                        }
+                        None => false,
+                    }
+                });
+                let child_ns = match name.as_slice() {
+                    b"svg" => Namespace::Svg,
+                    b"math" => Namespace::MathMl,
+                    _ => *namespace,
+                };
+                let child_descendant_of_pre =
+                    descendant_of_pre || (*namespace == Namespace::Html && name == b"pre");
+                *children = normalise(
+                    child_ns,
+                    child_descendant_of_pre,
+                    name,
+                    std::mem::take(children),
+                );
+                if *namespace == Namespace::Html && name == b"table" {
+                    *children = insert_tbody_and_colgroup(std::mem::take(children));
+                };
+            }
+            NodeData::ScriptOrStyleContent { code, .. } => {
+                right_trim(code);
+                left_trim(code);
+            }
+            NodeData::Text { value, .. } => {
+                if ws.trim && first == Some(i) {
+                    left_trim(value);
+                };
+                if ws.trim && last == Some(i) {
+                    right_trim(value);
+                };
+                if ws.destroy_whole && value.iter().all(u8::is_ascii_whitespace) {
+                    value.clear();
+                } else if ws.collapse {
+                    collapse_whitespace(value);
+                };
+            }
+            _ => {}
+        };
+    }
+    nodes.retain(|n| match n {
+        NodeData::ScriptOrStyleContent { code: value, .. } | NodeData::Text { value, .. } => {
+            !value.is_empty()
+        }
+        _ => true,
+    });
+    nodes
+}
+
+fn parse(src: &[u8]) -> Vec<NodeData> {
+    let mut code = Code::new_with_opts(src, &[]);
+    let parsed = parse_content(&mut code, Namespace::Html, EMPTY_SLICE, EMPTY_SLICE);
+    normalise(
+        Namespace::Html,
+        false,
+        EMPTY_SLICE,
+        insert_html_head_and_body(parsed.children),
+    )
+}
+
+// The name of a node in a path.
+fn path_name(node: &NodeData) -> String {
+    match node {
+        NodeData::Doctype { .. } => "doctype()".to_string(),
+        NodeData::Element { name, .. } => String::from_utf8_lossy(name).into_owned(),
+        NodeData::Verbatim { .. } => "verbatim()".to_string(),
+        _ => "text()".to_string(),
+    }
+}
+
+// Serialises a node, or only the opening tag of an element, for a `Difference`.
+fn describe(node: Option<&NodeData>) -> String {
+    let cfg = C14nCfg {
+        double_quote_attributes: true,
+        ..C14nCfg::default()
+    };
+    let mut out = Vec::new();
+    match node {
+        None => {}
+        Some(NodeData::Element {
+            attributes,
+            closing_tag,
+            name,
+            ..
+        }) => {
+            let mut attrs_sorted = attributes.iter().collect::<Vec<_>>();
+            attrs_sorted.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            c14n_serialise_start_tag(&mut out, &cfg, name, attrs_sorted, *closing_tag).unwrap();
+        }
+        Some(n) => c14n_serialise_ast(&mut out, &cfg, n).unwrap(),
+    };
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Whether two nodes are the same apart from their attributes and children.
+fn same_node(a: &NodeData, b: &NodeData) -> bool {
+    match (a, b) {
+        (NodeData::Doctype { .. }, NodeData::Doctype { .. }) => true,
+        (
+            NodeData::Element {
+                name: n1,
+                namespace: ns1,
+                ..
+            },
+            NodeData::Element {
+                name: n2,
+                namespace: ns2,
+                ..
+            },
+        ) => n1 == n2 && ns1 == ns2,
+        (NodeData::RcdataContent { text: a, .. }, NodeData::RcdataContent { text: b, .. })
+        | (
+            NodeData::ScriptOrStyleContent { code: a, .. },
+            NodeData::ScriptOrStyleContent { code: b, .. },
+        )
+        | (NodeData::Text { value: a, .. }, NodeData::Text { value: b, .. })
+        | (NodeData::Verbatim { code: a, .. }, NodeData::Verbatim { code: b, .. }) => a == b,
+        _ => false,
+    }
+}
+
+fn compare(path: &str, a: &[NodeData], b: &[NodeData]) -> Result<(), Difference> {
+    // Number of nodes seen with each path name, for numbering them.
+    let mut seen = FxHashMap::<String, usize>::default();
+    for i in 0..a.len().max(b.len()) {
+        let (x, y) = (a.get(i), b.get(i));
+        let name = path_name(x.or(y).unwrap());
+        let count = seen.entry(name.clone()).or_default();
+        *count += 1;
+        let path = format!("{path}/{name}[{count}]");
+        let (Some(x), Some(y)) = (x, y) else {
+            return Err(Difference {
+                path,
+                left: describe(x),
+                right: describe(y),
+            });
+        };
+        if !same_node(x, y) {
+            return Err(Difference {
+                path,
+                left: describe(Some(x)),
+                right: describe(Some(y)),
+            });
+        };
+        let (
+            NodeData::Element {
+                attributes: attrs_x,
+                children: children_x,
+                ..
+            },
+            NodeData::Element {
+                attributes: attrs_y,
+                children: children_y,
+                ..
+            },
+        ) = (x, y)
+        else {
+            continue;
+        };
+        let mut names = attrs_x.keys().chain(attrs_y.keys()).collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+        for attr in names {
+            let (vx, vy) = (attrs_x.get(attr), attrs_y.get(attr));
+            if vx.map(|v| &v.value) == vy.map(|v| &v.value) {
+                continue;
+            };
+            let describe_attr = |v: Option<&AttrVal>| {
+                v.map_or_else(String::new, |v| {
+                    format!(
+                        "{}=\"{}\"",
+                        String::from_utf8_lossy(attr),
+                        String::from_utf8_lossy(&v.value)
+                    )
+                })
+            };
+            return Err(Difference {
+                path: format!("{path}/@{}", String::from_utf8_lossy(attr)),
+                left: describe_attr(vx),
+                right: describe_attr(vy),
+            });
+        }
+        compare(&path, children_x, children_y)?;
+    }
+    Ok(())
+}
+
+// Parses and normalises both documents, then compares them node by node.
+pub fn first_difference(a: &[u8], b: &[u8]) -> Result<(), Difference> {
+    compare("", &parse(a), &parse(b))
+}
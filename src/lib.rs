@@ -2,23 +2,34 @@
 
 use std::io::Write;
 
-pub use crate::cfg::Cfg;
-use crate::{
-    ast::c14n::c14n_serialise_ast,
-    minify::content::minify_content,
-    parse::{Code, content::parse_content},
-    spec::tag::{EMPTY_SLICE, ns::Namespace},
+use crate::minify::output::Output;
+pub use crate::{
+    ast::Span,
+    cfg::{
+        AttrOrder, C14nCfg, Cfg, CommentPredicate, CssOptions, CssTargets, EsTarget, FormatCfg,
+        JsOptions,
+    },
+    dom::{Attr, Document, Node, NodeKind, NodeMut, NodeRef},
+    equal::Difference,
+    report::{Report, Warning, WarningKind},
+    source_map::{Mapping, SourceMap},
+    spec::tag::ns::Namespace,
 };
 
 mod ast;
 mod cfg;
 mod code_gen;
+mod dom;
 mod entity;
+mod equal;
 mod minify;
 mod parse;
 mod pattern;
+mod report;
+mod source_map;
 mod spec;
 mod tag;
+mod template;
 #[cfg(test)]
 mod tests;
 mod whitespace;
@@ -43,27 +54,156 @@ mod whitespace;
 /// ```
 #[must_use]
 pub fn minify(src: &[u8], cfg: Option<Cfg>) -> Vec<u8> {
-    let mut code = Code::new_with_opts(src);
-    let parsed = parse_content(&mut code, Namespace::Html, EMPTY_SLICE, EMPTY_SLICE);
-    let mut out = Vec::with_capacity(src.len());
     let cfg = cfg.unwrap_or_default();
-    minify_content(
-        &cfg,
-        &mut out,
-        Namespace::Html,
-        false,
-        EMPTY_SLICE,
-        parsed.children,
-    );
-    out.shrink_to_fit();
-    out
+    let mut out = Output::buffered(src.len());
+    Document::parse_with_cfg(src, &cfg).minify_into(Some(cfg), &mut out);
+    // Writing to a buffer can't fail.
+    out.finish().unwrap()
 }
 
-pub fn canonicalize<T: Write>(out: &mut T, src: &[u8]) -> std::io::Result<()> {
-    let mut code = Code::new(src);
-    let parsed = parse_content(&mut code, Namespace::Html, EMPTY_SLICE, EMPTY_SLICE);
-    for c in parsed.children {
-        c14n_serialise_ast(out, &c)?;
+/// Minifies UTF-8 HTML code like [`minify`], writing the output to `out` in chunks as it's produced
+/// instead of returning it as one vector.
+///
+/// Only the output is streamed: all of `src` is parsed into a tree before anything is written, so
+/// this saves the memory of the output vector but not of the source or tree. There's no mode that
+/// reads the source in chunks. Warnings aren't returned; use [`Document::minify_to_writer`] for them.
+///
+/// # Examples
+///
+/// ```
+/// use simple_minify_html::minify_to_writer;
+///
+/// let mut out = Vec::new();
+/// minify_to_writer(b"<p>  Hello, world!  </p>", None, &mut out).unwrap();
+/// assert_eq!(out, b"<p>Hello, world!".to_vec());
+/// ```
+pub fn minify_to_writer<W: Write>(
+    src: &[u8],
+    cfg: Option<Cfg>,
+    out: &mut W,
+) -> std::io::Result<()> {
+    let cfg = cfg.unwrap_or_default();
+    Document::parse_with_cfg(src, &cfg)
+        .minify_to_writer(Some(cfg), out)
+        .map(|_| ())
+}
+
+/// Minifies UTF-8 HTML code like [`minify`], and also returns the problems found in the source
+/// that were worked around, such as unparsable CSS or JavaScript that was kept as is.
+///
+/// # Examples
+///
+/// ```
+/// use simple_minify_html::{WarningKind, minify_with_report};
+///
+/// let report = minify_with_report(b"<p>Hello</div> world<!-- x", None);
+/// assert_eq!(report.output, b"<p>Hello world".to_vec());
+/// assert_eq!(report.warnings[0].kind, WarningKind::MismatchedClosingTag);
+/// assert_eq!(report.warnings[0].offset, 8);
+/// assert_eq!(report.warnings[1].kind, WarningKind::UnclosedComment);
+/// ```
+#[must_use]
+pub fn minify_with_report(src: &[u8], cfg: Option<Cfg>) -> Report {
+    let cfg = cfg.unwrap_or_default();
+    Document::parse_with_cfg(src, &cfg).minify_with_report(Some(cfg))
+}
+
+/// Minifies UTF-8 HTML code like [`minify`], and also returns a source map from the output back to
+/// `src`, which is called `source_name` in the map.
+///
+/// # Examples
+///
+/// ```
+/// use simple_minify_html::minify_with_source_map;
+///
+/// let (output, map) = minify_with_source_map(b"<div>\n  <p>Hello</p>\n</div>", None, "index.html");
+/// assert_eq!(output, b"<div><p>Hello</div>".to_vec());
+/// // The `<p>` at offset 5 in the output comes from line 2 of the source.
+/// assert_eq!(map.original_offset(5), Some(8));
+/// assert!(map.to_json().starts_with(r#"{"version":3,"sources":["index.html"]"#));
+/// ```
+#[must_use]
+pub fn minify_with_source_map(
+    src: &[u8],
+    cfg: Option<Cfg>,
+    source_name: &str,
+) -> (Vec<u8>, SourceMap) {
+    let cfg = cfg.unwrap_or_default();
+    let mut out = Output::buffered(src.len()).with_source_map();
+    Document::parse_with_cfg(src, &cfg).minify_into(Some(cfg), &mut out);
+    let mappings = out.take_mappings();
+    // Writing to a buffer can't fail.
+    let output = out.finish().unwrap();
+    let map = SourceMap::new(source_name, src, &output, mappings);
+    (output, map)
+}
+
+/// Minifies UTF-8 HTML code like [`minify`], but fails with the problems found in the source if there
+/// were any. See [`minify_with_report`].
+pub fn try_minify(src: &[u8], cfg: Option<Cfg>) -> Result<Vec<u8>, Vec<Warning>> {
+    let Report { output, warnings } = minify_with_report(src, cfg);
+    if warnings.is_empty() {
+        Ok(output)
+    } else {
+        Err(warnings)
     }
-    Ok(())
+}
+
+/// Parses and serialises UTF-8 HTML code without minifying it, resolving malformed syntax the same
+/// way the minifier does. Use [`Document`] to inspect or rewrite the tree in between, and
+/// [`C14nCfg`] to normalise more of the output.
+///
+/// ```
+/// use simple_minify_html::{C14nCfg, canonicalize};
+///
+/// let cfg = C14nCfg {
+///     double_quote_attributes: true,
+///     insert_html_head_and_body: true,
+///     insert_omitted_closing_tags: true,
+///     ..C14nCfg::default()
+/// };
+/// let mut out = Vec::new();
+/// canonicalize(&mut out, b"<title>A</title><p class=x>B", &cfg).unwrap();
+/// assert_eq!(
+///     out,
+///     b"<html><head><title>A</title></head><body><p class=\"x\">B</p></body></html>"
+/// );
+/// ```
+pub fn canonicalize<T: Write>(out: &mut T, src: &[u8], cfg: &C14nCfg) -> std::io::Result<()> {
+    Document::parse(src).canonicalize(out, cfg)
+}
+
+/// Checks whether two UTF-8 HTML documents render the same, returning where they first differ if
+/// not. Both are parsed and normalised the way the minifier treats them: whitespace is compared
+/// according to where it's significant, omitted tags and implied `html`, `head`, `body`, `tbody`,
+/// and `colgroup` elements are ignored, as are attribute order, quoting, entity forms, default
+/// attribute values, comments, and bangs. Code in `<script>` and `<style>` elements and in `style`
+/// and event handler attributes is compared as text, so compare with the `css` and `js` features
+/// disabled to check other minifications.
+///
+/// ```
+/// use simple_minify_html::{minify, semantically_equal};
+///
+/// let src = b"<html>\n  <body>\n    <p class='a  b'>Hello, &amp; <b>world</b></p>\n  </body>\n</html>";
+/// semantically_equal(src, &minify(src, None)).unwrap();
+///
+/// let diff = semantically_equal(b"<ul><li>1<li>2</ul>", b"<ul><li>1<li>3</ul>").unwrap_err();
+/// assert_eq!(diff.path, "/html[1]/body[1]/ul[1]/li[2]/text()[1]");
+/// ```
+pub fn semantically_equal(a: &[u8], b: &[u8]) -> Result<(), Difference> {
+    equal::first_difference(a, b)
+}
+
+/// Parses UTF-8 HTML code and writes it back indented, with lines broken only where whitespace is
+/// insignificant or already present, so the document renders the same. See [`FormatCfg`].
+///
+/// ```
+/// use simple_minify_html::{FormatCfg, format};
+///
+/// let mut out = Vec::new();
+/// format(&mut out, b"<ul><li>One<li>Two</ul>", &FormatCfg::default()).unwrap();
+/// assert_eq!(out, b"<ul>\n  <li>One\n  <li>Two\n</ul>\n");
+/// ```
+pub fn format<T: Write>(out: &mut T, src: &[u8], cfg: &FormatCfg) -> std::io::Result<()> {
+    Document::parse(src).format(out, cfg)
 }
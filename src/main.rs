@@ -0,0 +1,378 @@
+#![deny(unsafe_code)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Component, Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+use clap::{ArgGroup, Parser};
+use rayon::prelude::*;
+use simple_minify_html::{
+    AttrOrder, Cfg, CommentPredicate, CssOptions, CssTargets, EsTarget, JsOptions,
+    minify_with_report,
+};
+
+/// Minifies HTML files. Directories are searched recursively for `.html` and `.htm` files.
+#[derive(Parser)]
+#[command(version, group(ArgGroup::new("dest").required(true).args(["in_place", "output_dir"])))]
+struct Args {
+    /// Files, directories, or glob patterns (e.g. `site/**/*.html`) to minify.
+    #[arg(required = true)]
+    inputs: Vec<String>,
+    /// Overwrite the input files with their minified output.
+    #[arg(short, long)]
+    in_place: bool,
+    /// Write minified files to this directory, keeping their paths relative to the directory or
+    /// glob they were found in.
+    #[arg(short, long)]
+    output_dir: Option<PathBuf>,
+    /// Don't print per-file byte savings and warnings.
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// The order to write attributes in: `quoted-first`, `frequency`, `name`, or `source`.
+    #[arg(long, value_parser = parse_attr_order, default_value = "quoted-first")]
+    attr_order: AttrOrder,
+    /// Drop invalid CSS rules and declarations instead of leaving the whole stylesheet as is.
+    #[arg(long)]
+    css_error_recovery: bool,
+    /// Remove at-rules that aren't known, such as `@tailwind`, instead of keeping them as they are.
+    #[arg(long)]
+    css_remove_unknown_at_rules: bool,
+    /// Browsers that minified CSS must support, e.g. `chrome 95` or `safari >= 13.1`. Can be repeated.
+    #[arg(long, value_name = "QUERY")]
+    css_target: Vec<String>,
+    /// Replace invalid UTF-8 in CSS and JavaScript with U+FFFD instead of leaving that code as is.
+    #[arg(long)]
+    decode_invalid_utf8_lossily: bool,
+    /// Remove `console.*` calls.
+    #[arg(long)]
+    js_drop_console: bool,
+    /// Keep `debugger` statements.
+    #[arg(long)]
+    js_keep_debugger: bool,
+    /// Keep the `name` property of functions and classes the same.
+    #[arg(long)]
+    js_keep_fn_names: bool,
+    /// Don't shorten the names of local variables and functions.
+    #[arg(long)]
+    js_no_mangle: bool,
+    /// Also shorten names declared in the top-level scope.
+    #[arg(long)]
+    js_mangle_top_level: bool,
+    /// ECMAScript version that minified JavaScript may use syntax from, e.g. `es2015` or `esnext`.
+    #[arg(long, value_parser = parse_es_target, default_value = "esnext")]
+    js_target: EsTarget,
+    /// Do not omit `<body>`, `<colgroup>`, and `<tbody>` opening tags when they don't have attributes.
+    #[arg(long)]
+    keep_body_colgroup_and_tbody_opening_tags: bool,
+    /// Do not omit closing tags when possible.
+    #[arg(long)]
+    keep_closing_tags: bool,
+    /// Keep all comments.
+    #[arg(long)]
+    keep_comments: bool,
+    /// Keep comments starting with this, e.g. `!` for `<!--! License -->`. Can be repeated.
+    #[arg(long, value_name = "PREFIX")]
+    keep_comments_starting_with: Vec<String>,
+    /// Keep IE conditional comments, such as `<!--[if mso]>...<![endif]-->`.
+    #[arg(long)]
+    keep_conditional_comments: bool,
+    /// Do not omit `<html>` and `<head>` opening tags when they don't have attributes.
+    #[arg(long)]
+    keep_html_and_head_opening_tags: bool,
+    /// Keep `type=text` attribute name and value on `<input>` elements.
+    #[arg(long)]
+    keep_input_type_text_attr: bool,
+    /// Keep SSI comments.
+    #[arg(long)]
+    keep_ssi_comments: bool,
+    /// Minify the HTML inside kept conditional comments.
+    #[arg(long)]
+    minify_conditional_comments: bool,
+    /// Shorten the numbers in SVG path data and `points` and `viewBox` attributes.
+    #[arg(long)]
+    minify_svg_path_data: bool,
+    /// Remove all bangs.
+    #[arg(long)]
+    remove_bangs: bool,
+    /// Remove repeated names from `class` attributes, keeping the first occurrence of each.
+    #[arg(long)]
+    remove_duplicate_classes: bool,
+    /// Remove all processing instructions.
+    #[arg(long)]
+    remove_processing_instructions: bool,
+    /// Sort the names in `class` attributes.
+    #[arg(long)]
+    sort_classes: bool,
+    /// Keep template tags delimited by these as they are, e.g. `--template-delimiter '[[' ']]'`. Can be
+    /// repeated.
+    #[arg(long, num_args = 2, value_names = ["OPEN", "CLOSE"])]
+    template_delimiter: Vec<String>,
+    /// Keep `{{ }}`, `{% %}`, `{# #}`, and `<% %>` template tags as they are.
+    #[arg(long)]
+    template_syntax: bool,
+}
+
+fn parse_attr_order(s: &str) -> Result<AttrOrder, String> {
+    Ok(match s {
+        "quoted-first" => AttrOrder::QuotedFirst,
+        "frequency" => AttrOrder::Frequency,
+        "name" => AttrOrder::Name,
+        "source" => AttrOrder::Source,
+        _ => return Err(format!("unknown attribute order `{s}`")),
+    })
+}
+
+fn parse_es_target(s: &str) -> Result<EsTarget, String> {
+    Ok(match s.to_ascii_lowercase().as_str() {
+        "es5" => EsTarget::Es5,
+        "es2015" | "es6" => EsTarget::Es2015,
+        "es2016" => EsTarget::Es2016,
+        "es2017" => EsTarget::Es2017,
+        "es2018" => EsTarget::Es2018,
+        "es2019" => EsTarget::Es2019,
+        "es2020" => EsTarget::Es2020,
+        "es2021" => EsTarget::Es2021,
+        "es2022" => EsTarget::Es2022,
+        "es2023" => EsTarget::Es2023,
+        "es2024" => EsTarget::Es2024,
+        "es2025" => EsTarget::Es2025,
+        "esnext" => EsTarget::EsNext,
+        _ => return Err(format!("unknown ECMAScript version `{s}`")),
+    })
+}
+
+impl Args {
+    fn cfg(&self) -> Result<Cfg, String> {
+        Ok(Cfg {
+            attr_order: self.attr_order,
+            css: CssOptions {
+                error_recovery: self.css_error_recovery,
+                preserve_unknown_at_rules: !self.css_remove_unknown_at_rules,
+                targets: CssTargets::from_queries(&self.css_target)?,
+            },
+            decode_invalid_utf8_lossily: self.decode_invalid_utf8_lossily,
+            js: JsOptions {
+                drop_console: self.js_drop_console,
+                drop_debugger: !self.js_keep_debugger,
+                keep_fn_names: self.js_keep_fn_names,
+                mangle: !self.js_no_mangle,
+                mangle_top_level: self.js_mangle_top_level,
+                target: self.js_target,
+            },
+            keep_body_colgroup_and_tbody_opening_tags: self
+                .keep_body_colgroup_and_tbody_opening_tags,
+            keep_closing_tags: self.keep_closing_tags,
+            keep_comments: self.keep_comments,
+            keep_comments_if: (!self.keep_comments_starting_with.is_empty()).then(|| {
+                let prefixes = self.keep_comments_starting_with.clone();
+                Arc::new(move |c: &[u8]| prefixes.iter().any(|p| c.starts_with(p.as_bytes())))
+                    as CommentPredicate
+            }),
+            keep_conditional_comments: self.keep_conditional_comments,
+            keep_html_and_head_opening_tags: self.keep_html_and_head_opening_tags,
+            keep_input_type_text_attr: self.keep_input_type_text_attr,
+            keep_ssi_comments: self.keep_ssi_comments,
+            minify_conditional_comments: self.minify_conditional_comments,
+            minify_svg_path_data: self.minify_svg_path_data,
+            remove_bangs: self.remove_bangs,
+            remove_duplicate_classes: self.remove_duplicate_classes,
+            remove_processing_instructions: self.remove_processing_instructions,
+            sort_classes: self.sort_classes,
+            template_delimiters: {
+                let mut delims = if self.template_syntax {
+                    Cfg::common_template_delimiters()
+                } else {
+                    Vec::new()
+                };
+                delims.extend(
+                    self.template_delimiter
+                        .chunks(2)
+                        .map(|d| (d[0].clone().into_bytes(), d[1].clone().into_bytes())),
+                );
+                delims
+            },
+        })
+    }
+}
+
+// A file to minify, and its path relative to the input it was found from.
+struct Job {
+    src: PathBuf,
+    rel: PathBuf,
+}
+
+fn is_html_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm"))
+}
+
+fn collect_dir(root: &Path, dir: &Path, jobs: &mut Vec<Job>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        // Symlinks to directories aren't followed, as they could loop or lead out of the tree.
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_dir(root, &path, jobs)?;
+        } else if is_html_file(&path) && path.is_file() {
+            let rel = path.strip_prefix(root).unwrap().to_path_buf();
+            jobs.push(Job { src: path, rel });
+        };
+    }
+    Ok(())
+}
+
+// The leading components of a glob pattern that don't contain any pattern syntax.
+fn glob_root(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(
+            |c| !matches!(c, Component::Normal(s) if s.to_string_lossy().contains(['*', '?', '['])),
+        )
+        .collect()
+}
+
+fn collect_jobs(inputs: &[String]) -> Result<Vec<Job>, String> {
+    let mut jobs = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            collect_dir(path, path, &mut jobs).map_err(|e| format!("{input}: {e}"))?;
+        } else if path.is_file() {
+            jobs.push(Job {
+                src: path.to_path_buf(),
+                rel: PathBuf::from(path.file_name().unwrap()),
+            });
+        } else {
+            let root = glob_root(input);
+            let paths = glob::glob(input).map_err(|e| format!("{input}: {e}"))?;
+            let before = jobs.len();
+            for path in paths {
+                let path = path.map_err(|e| e.to_string())?;
+                if path.is_file() {
+                    let rel = path.strip_prefix(&root).unwrap_or(&path).to_path_buf();
+                    jobs.push(Job { src: path, rel });
+                };
+            }
+            if jobs.len() == before {
+                return Err(format!(
+                    "{input}: no such file, directory, or matching files"
+                ));
+            };
+        };
+    }
+    // Inputs can overlap, and minifying a file more than once would write to it concurrently.
+    let mut seen = HashSet::new();
+    jobs.retain(|job| seen.insert(fs::canonicalize(&job.src).unwrap_or_else(|_| job.src.clone())));
+    Ok(jobs)
+}
+
+// Fails if different files would be written to the same path in the output directory, such as
+// `a/index.html` and `b/index.html` given as files.
+fn check_destinations(jobs: &[Job]) -> Result<(), String> {
+    let mut dests = HashMap::<&Path, &Path>::new();
+    for job in jobs {
+        if let Some(other) = dests.insert(&job.rel, &job.src) {
+            return Err(format!(
+                "{} and {} would both be written to {}",
+                other.display(),
+                job.src.display(),
+                job.rel.display()
+            ));
+        };
+    }
+    Ok(())
+}
+
+// Returns the sizes before and after minification.
+fn minify_file(args: &Args, cfg: &Cfg, job: &Job) -> io::Result<(usize, usize)> {
+    let src = fs::read(&job.src)?;
+    let report = minify_with_report(&src, Some(cfg.clone()));
+    if !args.quiet {
+        for w in &report.warnings {
+            eprintln!("warning: {}:{w}", job.src.display());
+        }
+    };
+    let dest = match &args.output_dir {
+        Some(dir) => {
+            let dest = dir.join(&job.rel);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            };
+            dest
+        }
+        None => job.src.clone(),
+    };
+    fs::write(dest, &report.output)?;
+    Ok((src.len(), report.output.len()))
+}
+
+fn savings(before: usize, after: usize) -> String {
+    let pct = if before == 0 {
+        0.0
+    } else {
+        (before - after.min(before)) as f64 * 100.0 / before as f64
+    };
+    format!("{before} -> {after} bytes ({pct:.1}% smaller)")
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let cfg = match args.cfg() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let jobs = match collect_jobs(&args.inputs).and_then(|jobs| {
+        if args.output_dir.is_some() {
+            check_destinations(&jobs)?;
+        };
+        Ok(jobs)
+    }) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let results: Vec<_> = jobs
+        .par_iter()
+        .map(|job| (job, minify_file(&args, &cfg, job)))
+        .collect();
+
+    let mut failed = false;
+    let (mut total_before, mut total_after) = (0, 0);
+    for (job, res) in results {
+        match res {
+            Ok((before, after)) => {
+                total_before += before;
+                total_after += after;
+                if !args.quiet {
+                    println!("{}: {}", job.src.display(), savings(before, after));
+                };
+            }
+            Err(e) => {
+                failed = true;
+                eprintln!("error: {}: {e}", job.src.display());
+            }
+        };
+    }
+    if !args.quiet {
+        println!("Total: {}", savings(total_before, total_after));
+    };
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
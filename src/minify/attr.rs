@@ -1,17 +1,26 @@
-#[cfg(feature = "css")]
-use std::str::from_utf8;
 use std::sync::LazyLock;
 
 use aho_corasick::{AhoCorasickBuilder, AhoCorasickKind, MatchKind};
-#[cfg(feature = "css")]
-use lightningcss::stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleAttribute};
+use rustc_hash::FxHashSet;
 
 use crate::{
     Cfg,
-    code_gen::attrs::ATTRS,
+    ast::AttrVal,
+    code_gen::{
+        attrs::{ATTRS, AttributeMinification},
+        codepoints::NOT_UNQUOTED_ATTR_VAL_CHAR,
+        event_handlers::EVENT_HANDLER_ATTRS,
+    },
     entity::encode::encode_entities,
+    minify::{
+        css::minify_style_attr,
+        js::{minify_javascript_url, minify_js_attr},
+        svg::minify_svg_attr,
+    },
     pattern::Replacer,
+    report::Warning,
     spec::{script::JAVASCRIPT_MIME_TYPES, tag::ns::Namespace},
+    template::{contains_template, split_templates},
     whitespace::{collapse_whitespace, left_trim, remove_all_whitespace, right_trim},
 };
 
@@ -165,21 +174,128 @@ pub fn encode_unquoted(val: &[u8]) -> AttrMinifiedValue {
     }
 }
 
+// Encodes the text around the template tags in a value, leaving the template tags as they are. The
+// source quote is kept, as the template tags may rely on it; an unquoted value is only quoted if its
+// template tags contain characters that would otherwise end it.
+fn encode_templated(cfg: &Cfg, value: &AttrVal) -> AttrMinifiedValue {
+    let delims = cfg.template_delimiters.as_slice();
+    let templates_contain = |pred: &dyn Fn(u8) -> bool| {
+        split_templates(delims, &value.value).any(|(t, s)| t && s.iter().any(|&c| pred(c)))
+    };
+    let quote = value.quote.or_else(|| {
+        templates_contain(&|c| NOT_UNQUOTED_ATTR_VAL_CHAR[c]).then(|| {
+            if templates_contain(&|c| c == b'"') {
+                b'\''
+            } else {
+                b'"'
+            }
+        })
+    });
+    let mut data = Vec::with_capacity(value.value.len());
+    for (is_template, s) in split_templates(delims, &value.value) {
+        if is_template {
+            data.extend_from_slice(s);
+            continue;
+        };
+        let encoded = encode_entities(s, true);
+        data.extend_from_slice(&match quote {
+            Some(b'"') => WHATWG_DOUBLE_QUOTED_REPLACER.replace_all(&encoded),
+            Some(_) => WHATWG_SINGLE_QUOTED_REPLACER.replace_all(&encoded),
+            None => WHATWG_UNQUOTED_REPLACER.replace_all(&encoded),
+        });
+    }
+    let delim: &'static [u8] = match quote {
+        Some(b'"') => b"\"",
+        Some(_) => b"'",
+        None => b"",
+    };
+    AttrMinifiedValue {
+        quoted: quote.is_some(),
+        prefix: delim,
+        data,
+        start: 0,
+        suffix: delim,
+    }
+}
+
 pub enum AttrMinified {
     Redundant,
     NoValue,
     Value(AttrMinifiedValue),
 }
 
+fn minify_classes(cfg: &Cfg, value: &mut Vec<u8>) {
+    let mut classes = value
+        .split(u8::is_ascii_whitespace)
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>();
+    if cfg.sort_classes {
+        classes.sort_unstable();
+    };
+    if cfg.remove_duplicate_classes {
+        let mut seen = FxHashSet::default();
+        classes.retain(|c| seen.insert(*c));
+    };
+    *value = classes.join(b" ".as_slice());
+}
+
+// Trims and collapses whitespace in a value where the attribute allows it.
+pub fn normalise_attr_whitespace(
+    attr_cfg: Option<&AttributeMinification>,
+    // True if element is <meta> and has an attribute `name` equal to `viewport`.
+    is_meta_viewport: bool,
+    value: &mut Vec<u8>,
+) {
+    if is_meta_viewport {
+        remove_all_whitespace(value);
+    } else {
+        if attr_cfg.is_some_and(|attr| attr.trim) {
+            right_trim(value);
+            left_trim(value);
+        };
+        if attr_cfg.is_some_and(|attr| attr.collapse) {
+            collapse_whitespace(value);
+        };
+    };
+}
+
+// Lowercases a value if the attribute is case insensitive. This is done before checking it with
+// `is_redundant_attr`.
+pub fn normalise_attr_case(attr_cfg: Option<&AttributeMinification>, value: &mut [u8]) {
+    if attr_cfg.is_some_and(|attr| attr.case_insensitive) {
+        value.make_ascii_lowercase();
+    };
+}
+
+// Whether an attribute with this value is the same as not having it.
+pub fn is_redundant_attr(
+    attr_cfg: Option<&AttributeMinification>,
+    tag: &[u8],
+    name: &[u8],
+    value: &[u8],
+) -> bool {
+    // An attribute can have both redundant_if_empty and default_value, which means it has two default values: "" and default_value.
+    (value.is_empty() && attr_cfg.is_some_and(|attr| attr.redundant_if_empty))
+        || attr_cfg.and_then(|attr| attr.default_value) == Some(value)
+        || (tag == b"script" && name == b"type" && JAVASCRIPT_MIME_TYPES.contains(value))
+}
+
 pub fn minify_attr(
     cfg: &Cfg,
+    warnings: &mut Vec<Warning>,
     ns: Namespace,
     tag: &[u8],
     // True if element is <meta> and has an attribute `name` equal to `viewport`.
     is_meta_viewport: bool,
     name: &[u8],
-    mut value_raw: Vec<u8>,
+    value: AttrVal,
 ) -> AttrMinified {
+    // Template tags can render to anything, so the value can't be minified or omitted.
+    if contains_template(&cfg.template_delimiters, &value.value) {
+        return AttrMinified::Value(encode_templated(cfg, &value));
+    };
+
+    let mut value_raw = value.value;
     let attr_cfg = ATTRS.get(ns, tag, name);
 
     let do_not_omit = cfg.keep_input_type_text_attr
@@ -187,65 +303,28 @@ pub fn minify_attr(
         && name == b"type"
         && value_raw.eq_ignore_ascii_case(b"text");
 
-    let should_collapse = attr_cfg.filter(|attr| attr.collapse).is_some();
-    let should_trim = attr_cfg.filter(|attr| attr.trim).is_some();
-    let should_lowercase = attr_cfg.filter(|attr| attr.case_insensitive).is_some();
     let is_boolean = attr_cfg.filter(|attr| attr.boolean).is_some();
-    // An attribute can have both redundant_if_empty and default_value, which means it has two default values: "" and default_value.
-    let redundant_if_empty = attr_cfg.filter(|attr| attr.redundant_if_empty).is_some();
-    let default_value = attr_cfg.and_then(|attr| attr.default_value);
 
-    if is_meta_viewport {
-        remove_all_whitespace(&mut value_raw);
-    } else {
-        // Trim before checking is_boolean as the entire attribute could be redundant post-minification.
-        if should_trim {
-            right_trim(&mut value_raw);
-            left_trim(&mut value_raw);
-        };
-        if should_collapse {
-            collapse_whitespace(&mut value_raw);
-        };
-    };
+    // Trim before checking is_boolean as the entire attribute could be redundant post-minification.
+    normalise_attr_whitespace(attr_cfg, is_meta_viewport, &mut value_raw);
 
-    #[cfg(feature = "css")]
     if name == b"style" {
-        let result = match StyleAttribute::parse(
-            from_utf8(&value_raw).expect("`style` attribute value contains non-UTF-8"),
-            ParserOptions::default(),
-        ) {
-            Ok(mut sty) => {
-                sty.minify(MinifyOptions::default());
-                let popt = PrinterOptions {
-                    minify: true,
-                    ..Default::default()
-                };
-                match sty.to_css(popt) {
-                    Ok(out) => Some(out.code),
-                    // TODO Collect error as warning.
-                    Err(_err) => None,
-                }
-            }
-            // TODO Collect error as warning.
-            Err(_err) => None,
-        };
-        if let Some(min) = result {
-            value_raw = min.into_bytes();
-        };
-    }
+        minify_style_attr(cfg, warnings, &mut value_raw, value.span.start);
+    } else if EVENT_HANDLER_ATTRS.contains(name) {
+        minify_js_attr(cfg, warnings, &mut value_raw, value.span.start);
+    } else if name == b"href" {
+        minify_javascript_url(cfg, warnings, &mut value_raw, value.span.start);
+    } else if ns == Namespace::Svg {
+        minify_svg_attr(cfg, warnings, name, &mut value_raw, value.span.start);
+    };
 
-    // Make lowercase before checking against default value or JAVASCRIPT_MIME_TYPES.
-    if should_lowercase {
-        value_raw.make_ascii_lowercase();
+    if name == b"class" && (cfg.remove_duplicate_classes || cfg.sort_classes) {
+        minify_classes(cfg, &mut value_raw);
     };
 
-    if !do_not_omit
-        && ((value_raw.is_empty() && redundant_if_empty)
-            || default_value.filter(|dv| dv == &value_raw).is_some()
-            || (tag == b"script"
-                && name == b"type"
-                && JAVASCRIPT_MIME_TYPES.contains(value_raw.as_slice())))
-    {
+    normalise_attr_case(attr_cfg, &mut value_raw);
+
+    if !do_not_omit && is_redundant_attr(attr_cfg, tag, name, &value_raw) {
         return AttrMinified::Redundant;
     };
 
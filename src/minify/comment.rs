@@ -1,10 +1,85 @@
-use crate::cfg::Cfg;
+use memchr::memmem;
 
-pub fn minify_comment(cfg: &Cfg, out: &mut Vec<u8>, code: &[u8], ended: bool) {
+use crate::{
+    cfg::Cfg,
+    minify::{content::minify_content, output::Output},
+    parse::{Code, content::parse_content},
+    spec::tag::{EMPTY_SLICE, ns::Namespace},
+};
+
+const ENDIF: &[u8] = b"<![endif]";
+
+fn starts_with_ignore_case(code: &[u8], prefix: &[u8]) -> bool {
+    code.get(..prefix.len())
+        .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+}
+
+// Downlevel-hidden conditional comments (`<!--[if IE]>...<![endif]-->`), and both halves of
+// downlevel-revealed ones (`<!--[if !IE]><!-->...<!--<![endif]-->`).
+fn is_conditional_comment(code: &[u8]) -> bool {
+    starts_with_ignore_case(code, b"[if") || code.eq_ignore_ascii_case(ENDIF)
+}
+
+pub fn is_comment_kept(cfg: &Cfg, code: &[u8]) -> bool {
     let is_ssi = code.starts_with(b"#");
-    if cfg.keep_comments || (is_ssi && cfg.keep_ssi_comments) {
+    cfg.keep_comments
+        || (is_ssi && cfg.keep_ssi_comments)
+        || (cfg.keep_conditional_comments && is_conditional_comment(code))
+        || cfg.keep_comments_if.as_ref().is_some_and(|f| f(code))
+}
+
+// Minifies the HTML inside a downlevel-hidden conditional comment. Returns None if the comment has
+// no HTML inside, or the HTML had problems such as closing tags for elements opened outside the
+// comment, as minifying it would then drop them.
+fn minify_conditional_comment(cfg: &Cfg, code: &[u8]) -> Option<Vec<u8>> {
+    if !starts_with_ignore_case(code, b"[if")
+        || code.len() < ENDIF.len()
+        || !code[code.len() - ENDIF.len()..].eq_ignore_ascii_case(ENDIF)
+    {
+        return None;
+    };
+    let start = code.iter().position(|&c| c == b'>')? + 1;
+    let end = code.len() - ENDIF.len();
+    if start > end {
+        return None;
+    };
+
+    let mut inner = Code::new_with_opts(&code[start..end], &cfg.template_delimiters);
+    let parsed = parse_content(&mut inner, Namespace::Html, EMPTY_SLICE, EMPTY_SLICE);
+    if !inner.warnings.is_empty() {
+        return None;
+    };
+    let mut out = Output::buffered(code.len());
+    out.extend_from_slice(&code[..start]);
+    // Problems inside are already handled by leaving that code as is, and their offsets would be
+    // relative to the comment, so warnings aren't reported.
+    minify_content(
+        cfg,
+        &mut out,
+        &mut Vec::new(),
+        Namespace::Html,
+        false,
+        EMPTY_SLICE,
+        parsed.children,
+    );
+    out.extend_from_slice(ENDIF);
+    let out = out.finish().ok()?;
+    // Decoded entities could otherwise end the comment early.
+    if memmem::find(&out, b"-->").is_some() || memmem::find(&out, b"--!>").is_some() {
+        return None;
+    };
+    Some(out)
+}
+
+pub fn minify_comment(cfg: &Cfg, out: &mut Vec<u8>, code: &[u8], ended: bool) {
+    if is_comment_kept(cfg, code) {
         out.extend_from_slice(b"<!--");
-        out.extend_from_slice(code);
+        let min = if ended && cfg.minify_conditional_comments {
+            minify_conditional_comment(cfg, code)
+        } else {
+            None
+        };
+        out.extend_from_slice(min.as_deref().unwrap_or(code));
         if ended {
             out.extend_from_slice(b"-->");
         };
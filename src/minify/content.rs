@@ -8,14 +8,24 @@ use crate::{
     cfg::Cfg,
     entity::encode::encode_entities,
     minify::{
-        bang::minify_bang, comment::minify_comment, css::minify_css, doctype::minify_doctype,
-        element::minify_element, instruction::minify_instruction, js::minify_js,
+        bang::minify_bang,
+        comment::{is_comment_kept, minify_comment},
+        css::minify_css,
+        doctype::minify_doctype,
+        element::minify_element,
+        instruction::minify_instruction,
+        js::minify_js,
+        json::minify_json,
+        output::Output,
     },
+    parse::style::decode_svg_style,
     pattern::Replacer,
+    report::Warning,
     spec::tag::{
         ns::Namespace,
         whitespace::{WhitespaceMinification, get_whitespace_minification_for_tag},
     },
+    template::contains_template,
     whitespace::{collapse_whitespace, is_all_whitespace, left_trim, right_trim},
 };
 
@@ -31,22 +41,68 @@ fn build_whatwg_chevron_replacer() -> Replacer {
 
 static WHATWG_CHEVRON_REPLACER: LazyLock<Replacer> = LazyLock::new(build_whatwg_chevron_replacer);
 
+// Whether a node is a comment, bang, or processing instruction that won't be written.
+pub fn is_removed(cfg: &Cfg, node: &NodeData) -> bool {
+    match node {
+        NodeData::Bang { .. } => cfg.remove_bangs,
+        NodeData::Comment { code, .. } => !is_comment_kept(cfg, code),
+        NodeData::Instruction { .. } => cfg.remove_processing_instructions,
+        _ => false,
+    }
+}
+
+// Trims and collapses the whitespace of a text node, where `is_first` is whether it's the first text
+// or element node of its parent. Whitespace at the end of the last one is trimmed separately.
+pub fn minify_text_whitespace(ws: &WhitespaceMinification, is_first: bool, value: &mut Vec<u8>) {
+    if is_first && ws.trim {
+        left_trim(value);
+    };
+    // Our parser is guaranteed to output contiguous text as a single node,
+    // so the adjacent nodes to a text node (not counting comments/bangs/etc.) should be elements.
+    // TODO debug_assert this and add tests.
+    if ws.destroy_whole && is_all_whitespace(value) {
+        value.clear();
+    } else if ws.collapse {
+        collapse_whitespace(value);
+    };
+}
+
+// Drops the comments, bangs, and processing instructions that won't be written, joining the text
+// around them. Text encoded separately could otherwise form an entity once joined, like in
+// `&a<!-- -->mp`.
+fn drop_removed(cfg: &Cfg, nodes: Vec<NodeData>) -> Vec<NodeData> {
+    let mut out = Vec::<NodeData>::with_capacity(nodes.len());
+    for n in nodes {
+        match n {
+            n if is_removed(cfg, &n) => {}
+            NodeData::Text { value, .. } if matches!(out.last(), Some(NodeData::Text { .. })) => {
+                let Some(NodeData::Text { value: prev, .. }) = out.last_mut() else {
+                    unreachable!();
+                };
+                prev.extend_from_slice(&value);
+            }
+            n => out.push(n),
+        };
+    }
+    out
+}
+
+#[allow(clippy::collapsible_if)]
 pub fn minify_content(
     cfg: &Cfg,
-    out: &mut Vec<u8>,
+    out: &mut Output,
+    warnings: &mut Vec<Warning>,
     ns: Namespace,
     descendant_of_pre: bool,
     // Use empty slice if none.
     parent: &[u8],
-    mut nodes: Vec<NodeData>,
+    nodes: Vec<NodeData>,
 ) {
-    let &WhitespaceMinification {
-        collapse,
-        destroy_whole,
-        trim,
-    } = get_whitespace_minification_for_tag(ns, parent, descendant_of_pre);
+    let ws = get_whitespace_minification_for_tag(ns, parent, descendant_of_pre);
 
-    // TODO Document or fix: even though bangs/comments/etc. don't affect layout, we don't collapse/destroy-whole/trim combined text nodes across bangs/comments/etc., as that's too complex and is ambiguous about which nodes should whitespace be deleted from.
+    let mut nodes = drop_removed(cfg, nodes);
+
+    // TODO Document or fix: even though kept bangs/comments/etc. don't affect layout, we don't collapse/destroy-whole/trim combined text nodes across them, as that's too complex and is ambiguous about which nodes should whitespace be deleted from.
     let mut found_first_text_or_elem = false;
     let mut index_of_last_nonempty_text_or_elem: isize = -1;
     let mut index_of_last_text_or_elem: isize = -1;
@@ -69,77 +125,120 @@ pub fn minify_content(
                 index_of_last_nonempty_text_or_elem = i as isize;
                 index_of_last_text_or_elem = i as isize;
             }
-            NodeData::Text { value } => {
-                if !found_first_text_or_elem {
-                    // This is the first element or text node, and it's a text node.
-                    found_first_text_or_elem = true;
-                    if trim {
-                        left_trim(value);
-                    };
-                };
-                // Our parser is guaranteed to output contiguous text as a single node,
-                // so the adjacent nodes to a text node (not counting comments/bangs/etc.) should be elements.
-                // TODO debug_assert this and add tests.
-                if destroy_whole && is_all_whitespace(value) {
-                    value.clear();
-                } else if collapse {
-                    collapse_whitespace(value);
-                };
+            NodeData::Text { value, .. } => {
+                minify_text_whitespace(ws, !found_first_text_or_elem, value);
+                found_first_text_or_elem = true;
                 // Set AFTER processing.
                 index_of_last_text_or_elem = i as isize;
                 if !value.is_empty() {
                     index_of_last_nonempty_text_or_elem = i as isize;
                 };
             }
+            // Treated like non-whitespace text, so that whitespace next to it is kept and tags next to
+            // it aren't omitted.
+            NodeData::Verbatim { .. } => {
+                found_first_text_or_elem = true;
+                index_of_last_nonempty_text_or_elem = i as isize;
+                index_of_last_text_or_elem = i as isize;
+            }
             _ => {}
         };
     }
-    if trim && index_of_last_text_or_elem > -1 {
-        if let NodeData::Text { value } =
+    if ws.trim && index_of_last_text_or_elem > -1 {
+        if let NodeData::Text { value, .. } =
             nodes.get_mut(index_of_last_text_or_elem as usize).unwrap()
         {
             right_trim(value);
         };
     }
 
+    // The tag name of the previous text or element node if it's an element, otherwise empty.
+    let mut previous_sibling_element_name = Vec::new();
     for (i, c) in nodes.into_iter().enumerate() {
+        // Nodes created rather than parsed have no source to map to.
+        let start = out.position();
+        let span = c.span();
+        if !span.is_empty() {
+            out.add_mapping(span.start);
+        };
         match c {
-            NodeData::Bang { code, ended } => minify_bang(cfg, out, &code, ended),
-            NodeData::Comment { code, ended } => minify_comment(cfg, out, &code, ended),
-            NodeData::Doctype { ended } => minify_doctype(out, ended),
+            NodeData::Bang { code, ended, .. } => minify_bang(cfg, out, &code, ended),
+            NodeData::Comment { code, ended, .. } => minify_comment(cfg, out, &code, ended),
+            NodeData::Doctype { ended, .. } => minify_doctype(out, ended),
             NodeData::Element {
                 attributes,
                 children,
                 closing_tag,
+                content_span,
                 name,
                 namespace: child_ns,
                 next_sibling_element_name,
-            } => minify_element(
-                cfg,
-                out,
-                descendant_of_pre,
-                child_ns,
-                parent,
-                &next_sibling_element_name,
-                (i as isize) == index_of_last_nonempty_text_or_elem,
-                &name,
-                attributes,
-                closing_tag,
-                children,
-            ),
-            NodeData::Instruction { code, ended } => minify_instruction(cfg, out, &code, ended),
-            NodeData::RcdataContent { typ, text } => minify_rcdata(out, typ, &text),
-            NodeData::ScriptOrStyleContent { code, lang: _ } if code.is_empty() => {}
-            NodeData::ScriptOrStyleContent { code, lang } => match lang {
-                ScriptOrStyleLang::CSS => minify_css(out, &code),
+                ..
+            } => {
+                minify_element(
+                    cfg,
+                    out,
+                    warnings,
+                    descendant_of_pre,
+                    child_ns,
+                    parent,
+                    &previous_sibling_element_name,
+                    &next_sibling_element_name,
+                    (i as isize) == index_of_last_nonempty_text_or_elem,
+                    &name,
+                    attributes,
+                    closing_tag,
+                    (!span.is_empty()).then_some(content_span.end),
+                    children,
+                );
+                previous_sibling_element_name = name;
+            }
+            NodeData::Instruction { code, ended, .. } => minify_instruction(cfg, out, &code, ended),
+            NodeData::RcdataContent { typ, text, .. } => minify_rcdata(cfg, out, typ, &text),
+            NodeData::ScriptOrStyleContent { code, .. } if code.is_empty() => {}
+            NodeData::ScriptOrStyleContent { code, .. }
+                if contains_template(&cfg.template_delimiters, &code) =>
+            {
+                out.extend_from_slice(&code)
+            }
+            NodeData::ScriptOrStyleContent { code, lang, .. } => match lang {
+                // Unlike in HTML, `<style>` code in SVG can have entities and CDATA sections, so it's
+                // decoded before minifying and encoded like text after.
+                ScriptOrStyleLang::CSS if ns != Namespace::Html => {
+                    // Offsets change when decoding and encoding, so the inner map can't be used.
+                    let mut min = Output::buffered(code.len());
+                    minify_css(
+                        cfg,
+                        &mut min,
+                        warnings,
+                        &decode_svg_style(&code),
+                        span.start,
+                    );
+                    let min = encode_entities(&min, false);
+                    out.extend_from_slice(&WHATWG_CHEVRON_REPLACER.replace_all(&min));
+                }
+                ScriptOrStyleLang::CSS => minify_css(cfg, out, warnings, &code, span.start),
                 ScriptOrStyleLang::Data => out.extend_from_slice(&code),
-                ScriptOrStyleLang::JS | ScriptOrStyleLang::JSModule => minify_js(out, &code),
+                ScriptOrStyleLang::JS => minify_js(cfg, out, warnings, &code, span.start, false),
+                ScriptOrStyleLang::JSModule => {
+                    minify_js(cfg, out, warnings, &code, span.start, true)
+                }
+                ScriptOrStyleLang::JSON => minify_json(out, warnings, &code, span.start),
             },
-            NodeData::Text { value } => {
+            NodeData::Text { value, .. } => {
+                if !value.is_empty() {
+                    previous_sibling_element_name.clear();
+                };
                 let min = encode_entities(&value, false);
                 let min = WHATWG_CHEVRON_REPLACER.replace_all(&min);
                 out.extend_from_slice(&min);
             }
+            NodeData::Verbatim { code, .. } => {
+                previous_sibling_element_name.clear();
+                out.extend_from_slice(&code);
+            }
         };
+        out.drop_unused_mapping(start);
+        out.maybe_flush();
     }
 }
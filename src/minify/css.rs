@@ -1,43 +1,370 @@
 #[cfg(feature = "css")]
-use std::str::from_utf8;
+use std::sync::{Arc, RwLock};
 
 #[cfg(feature = "css")]
-use lightningcss::stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet};
+use cssparser::{CowRcStr, ParseError, Parser, ParserState};
+#[cfg(feature = "css")]
+use lightningcss::{
+    error::{Error, ErrorLocation, ParserError, PrinterError},
+    printer::Printer,
+    stylesheet::{
+        MinifyOptions, ParserOptions, PrinterOptions, StyleAttribute, StyleSheet, ToCssResult,
+    },
+    targets::{Browsers, Targets},
+    traits::{AtRuleParser, ToCss},
+};
+#[cfg(feature = "css")]
+use parcel_sourcemap::SourceMap;
+
+use crate::{cfg::Cfg, minify::output::Output, report::Warning, whitespace::trimmed};
+#[cfg(feature = "css")]
+use crate::{minify::utf8::decode_utf8, report::WarningKind, source_map::add_inner_mappings};
+
+// Converts a lightningcss error location into a byte offset into `code`.
+#[cfg(feature = "css")]
+fn css_error_offset(code: &str, loc: Option<&ErrorLocation>) -> usize {
+    let Some(loc) = loc else {
+        return 0;
+    };
+    let line_start = code
+        .split_inclusive('\n')
+        .take(loc.line as usize)
+        .map(str::len)
+        .sum::<usize>();
+    // Columns start from 1 and count characters, not bytes.
+    let column = code[line_start..]
+        .chars()
+        .take((loc.column as usize).saturating_sub(1))
+        .map(char::len_utf8)
+        .sum::<usize>();
+    line_start + column
+}
+
+#[cfg(feature = "css")]
+fn targets(cfg: &Cfg) -> Targets {
+    let t = &cfg.css.targets;
+    if t.is_empty() {
+        return Targets::default();
+    };
+    // lightningcss encodes versions as 0x00MMmmpp.
+    let v = |v: Option<(u8, u8)>| {
+        v.map(|(major, minor)| (u32::from(major) << 16) | (u32::from(minor) << 8))
+    };
+    Targets::from(Browsers {
+        android: v(t.android),
+        chrome: v(t.chrome),
+        edge: v(t.edge),
+        firefox: v(t.firefox),
+        ie: v(t.ie),
+        ios_saf: v(t.ios_saf),
+        opera: v(t.opera),
+        safari: v(t.safari),
+        samsung: v(t.samsung),
+    })
+}
 
-use crate::whitespace::trimmed;
+#[cfg(feature = "css")]
+type ParserWarnings<'i> = Arc<RwLock<Vec<Error<ParserError<'i>>>>>;
+
+// Parses unknown at-rules into a rule that's written as nothing, to remove them. Without this,
+// lightningcss keeps them as they are.
+#[cfg(feature = "css")]
+struct RemoveUnknownAtRules;
+
+#[cfg(feature = "css")]
+#[derive(Clone)]
+struct RemovedAtRule;
+
+#[cfg(feature = "css")]
+impl ToCss for RemovedAtRule {
+    fn to_css<W: std::fmt::Write>(&self, _dest: &mut Printer<W>) -> Result<(), PrinterError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "css")]
+impl<'i> AtRuleParser<'i> for RemoveUnknownAtRules {
+    type AtRule = RemovedAtRule;
+    type Error = ();
+    type Prelude = ();
+
+    fn parse_prelude<'t>(
+        &mut self,
+        _name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+        _options: &ParserOptions<'_, 'i>,
+    ) -> Result<(), ParseError<'i, ()>> {
+        while input.next().is_ok() {}
+        Ok(())
+    }
+
+    fn rule_without_block(
+        &mut self,
+        _prelude: (),
+        _start: &ParserState,
+        _options: &ParserOptions<'_, 'i>,
+        _is_nested: bool,
+    ) -> Result<RemovedAtRule, ()> {
+        Ok(RemovedAtRule)
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        _prelude: (),
+        _start: &ParserState,
+        input: &mut Parser<'i, 't>,
+        _options: &ParserOptions<'_, 'i>,
+        _is_nested: bool,
+    ) -> Result<RemovedAtRule, ParseError<'i, ()>> {
+        while input.next().is_ok() {}
+        Ok(RemovedAtRule)
+    }
+}
+
+// Minifies a parsed stylesheet, whichever way its at-rules were parsed.
+#[cfg(feature = "css")]
+fn minify_stylesheet<T: ToCss + Clone>(
+    mut sty: StyleSheet<'_, '_, T>,
+    targets: Targets,
+    popt: PrinterOptions,
+) -> Result<ToCssResult, (String, Option<ErrorLocation>)> {
+    sty.minify(MinifyOptions {
+        targets,
+        ..Default::default()
+    })
+    .map_err(|err| (err.to_string(), err.loc))?;
+    sty.to_css(popt).map_err(|err| (err.to_string(), err.loc))
+}
+
+#[cfg(feature = "css")]
+fn parser_options<'i>(cfg: &Cfg) -> (ParserOptions<'static, 'i>, ParserWarnings<'i>) {
+    let recovered = ParserWarnings::default();
+    let opts = ParserOptions {
+        error_recovery: cfg.css.error_recovery,
+        warnings: Some(recovered.clone()),
+        ..ParserOptions::default()
+    };
+    (opts, recovered)
+}
+
+// Reports the problems lightningcss found but continued past, which are invalid rules and
+// declarations that were dropped if error recovery is enabled.
+#[cfg(feature = "css")]
+fn report_recovered(
+    cfg: &Cfg,
+    warnings: &mut Vec<Warning>,
+    recovered: ParserWarnings,
+    src: &str,
+    offset: usize,
+) {
+    for err in recovered.read().unwrap().iter() {
+        // Unknown at-rules are kept as they are, which isn't a problem.
+        if matches!(err.kind, ParserError::AtRuleInvalid(_)) {
+            continue;
+        };
+        let message = if cfg.css.error_recovery {
+            format!("dropped invalid CSS: {err}")
+        } else {
+            format!("invalid CSS: {err}")
+        };
+        warnings.push(Warning {
+            kind: WarningKind::InvalidCss,
+            offset: offset + css_error_offset(src, err.loc.as_ref()),
+            message,
+        });
+    }
+}
 
 #[cfg(not(feature = "css"))]
-pub fn minify_css(out: &mut Vec<u8>, code: &[u8]) {
+pub fn minify_css(
+    _cfg: &Cfg,
+    out: &mut Output,
+    _warnings: &mut Vec<Warning>,
+    code: &[u8],
+    _offset: usize,
+) {
     out.extend_from_slice(trimmed(code));
 }
 
 #[cfg(feature = "css")]
-pub fn minify_css(out: &mut Vec<u8>, code: &[u8]) {
+pub fn minify_css(
+    cfg: &Cfg,
+    out: &mut Output,
+    warnings: &mut Vec<Warning>,
+    code: &[u8],
+    offset: usize,
+) {
+    let targets = targets(cfg);
+    let Some(src) = decode_utf8(cfg, warnings, code, offset) else {
+        out.extend_from_slice(trimmed(code));
+        return;
+    };
+    // The map is only useful if offsets into the decoded code are offsets into the source.
+    let mut source_map = (out.is_mapped() && src.len() == code.len()).then(|| {
+        let mut map = SourceMap::new("/");
+        map.add_source("style.css");
+        map
+    });
     let popt = PrinterOptions {
         minify: true,
+        targets,
+        source_map: source_map.as_mut(),
         ..Default::default()
     };
-    let result = match StyleSheet::parse(
-        from_utf8(code).expect("<style> content contains non-UTF-8"),
-        ParserOptions::default(),
-    ) {
-        Ok(mut sty) => match sty.minify(MinifyOptions::default()) {
-            Ok(()) => match sty.to_css(popt) {
-                Ok(out) => Some(out.code),
-                // TODO Collect error as warning.
-                Err(_err) => None,
-            },
-            // TODO Collect error as warning.
-            Err(_err) => None,
-        },
-        // TODO Collect error as warning.
-        Err(_err) => None,
-    };
-    if let Some(min) = result {
-        if min.len() < code.len() {
-            out.extend_from_slice(min.as_bytes());
+    let (parser_options, recovered) = parser_options(cfg);
+    let result = if cfg.css.preserve_unknown_at_rules {
+        StyleSheet::parse(&src, parser_options)
+            .map_err(|err| (err.to_string(), err.loc))
+            .and_then(|sty| minify_stylesheet(sty, targets, popt))
+    } else {
+        StyleSheet::parse_with(&src, parser_options, &mut RemoveUnknownAtRules)
+            .map_err(|err| (err.to_string(), err.loc))
+            .and_then(|sty| minify_stylesheet(sty, targets, popt))
+    };
+    report_recovered(cfg, warnings, recovered, &src, offset);
+    match result {
+        // Lowering or prefixing for targets can make the code longer, but is still wanted.
+        Ok(min) if min.code.len() < code.len() || !cfg.css.targets.is_empty() => {
+            if let Some(map) = source_map {
+                // Generated columns count bytes, but original ones count UTF-16 code units.
+                let tokens = map.get_mappings().into_iter().filter_map(|m| {
+                    let o = m.original?;
+                    Some([
+                        m.generated_line,
+                        m.generated_column,
+                        o.original_line,
+                        o.original_column,
+                    ])
+                });
+                add_inner_mappings(out, &min.code, 0, &src, offset, false, tokens);
+            };
+            out.extend_from_slice(min.code.as_bytes());
             return;
-        };
+        }
+        Ok(_) => {}
+        Err((message, loc)) => warnings.push(Warning {
+            kind: WarningKind::InvalidCss,
+            offset: offset + css_error_offset(&src, loc.as_ref()),
+            message,
+        }),
     };
     out.extend_from_slice(trimmed(code));
 }
+
+#[cfg(not(feature = "css"))]
+pub fn minify_style_attr(
+    _cfg: &Cfg,
+    _warnings: &mut Vec<Warning>,
+    _value: &mut Vec<u8>,
+    _offset: usize,
+) {
+}
+
+// Minifies a `style` attribute value in place, leaving it as is if it can't be parsed.
+#[cfg(feature = "css")]
+pub fn minify_style_attr(
+    cfg: &Cfg,
+    warnings: &mut Vec<Warning>,
+    value: &mut Vec<u8>,
+    offset: usize,
+) {
+    let Some(src) = decode_utf8(cfg, warnings, value, offset) else {
+        return;
+    };
+    let targets = targets(cfg);
+    let (parser_options, recovered) = parser_options(cfg);
+    let result = StyleAttribute::parse(&src, parser_options)
+        .map_err(|err| (err.to_string(), err.loc))
+        .and_then(|mut sty| {
+            sty.minify(MinifyOptions {
+                targets,
+                ..Default::default()
+            });
+            let popt = PrinterOptions {
+                minify: true,
+                targets,
+                ..Default::default()
+            };
+            sty.to_css(popt).map_err(|err| (err.to_string(), err.loc))
+        });
+    report_recovered(cfg, warnings, recovered, &src, offset);
+    match result {
+        Ok(min) => *value = min.code.into_bytes(),
+        Err((message, loc)) => warnings.push(Warning {
+            kind: WarningKind::InvalidCss,
+            offset: offset + css_error_offset(&src, loc.as_ref()),
+            message,
+        }),
+    };
+}
+
+// SVG presentation attributes with CSS color or number values, which are minified like the CSS
+// property of the same name. Others, like `transform`, have syntax that differs from CSS.
+#[cfg(feature = "css")]
+static PRESENTATION_ATTRS: &[&[u8]] = &[
+    b"color",
+    b"fill",
+    b"fill-opacity",
+    b"flood-color",
+    b"flood-opacity",
+    b"lighting-color",
+    b"opacity",
+    b"stop-color",
+    b"stop-opacity",
+    b"stroke",
+    b"stroke-opacity",
+];
+
+#[cfg(not(feature = "css"))]
+pub fn minify_presentation_attr(
+    _cfg: &Cfg,
+    _warnings: &mut Vec<Warning>,
+    _name: &[u8],
+    _value: &mut Vec<u8>,
+    _offset: usize,
+) {
+}
+
+// Minifies an SVG presentation attribute value in place, leaving it as is if it isn't valid CSS or
+// doesn't get any shorter. Invalid values aren't reported, as SVG allows some syntax that CSS doesn't.
+#[cfg(feature = "css")]
+pub fn minify_presentation_attr(
+    cfg: &Cfg,
+    warnings: &mut Vec<Warning>,
+    name: &[u8],
+    value: &mut Vec<u8>,
+    offset: usize,
+) {
+    if !PRESENTATION_ATTRS.contains(&name) || value.contains(&b';') {
+        return;
+    };
+    let Some(src) = decode_utf8(cfg, warnings, value, offset) else {
+        return;
+    };
+    let name = std::str::from_utf8(name).unwrap();
+    let decl = format!("{name}:{src}");
+    let targets = targets(cfg);
+    let Ok(mut sty) = StyleAttribute::parse(&decl, ParserOptions::default()) else {
+        return;
+    };
+    sty.minify(MinifyOptions {
+        targets,
+        ..Default::default()
+    });
+    let popt = PrinterOptions {
+        minify: true,
+        targets,
+        ..Default::default()
+    };
+    let Ok(min) = sty.to_css(popt) else {
+        return;
+    };
+    if let Some(min) = min
+        .code
+        .strip_prefix(name)
+        .and_then(|m| m.strip_prefix(':'))
+        .filter(|m| !m.is_empty() && !m.contains(';') && m.len() < value.len())
+    {
+        *value = min.as_bytes().to_vec();
+    };
+}
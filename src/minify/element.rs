@@ -1,38 +1,76 @@
-use rustc_hash::FxHashMap;
-
 use crate::{
-    ast::{AttrVal, ElementClosingTag, NodeData},
-    cfg::Cfg,
+    ast::{Attributes, ElementClosingTag, NodeData},
+    cfg::{AttrOrder, Cfg},
     minify::{
         attr::{AttrMinified, minify_attr},
-        content::minify_content,
+        content::{is_removed, minify_content, minify_text_whitespace},
+        output::Output,
     },
+    report::Warning,
     spec::tag::{
+        EMPTY_SLICE,
         ns::Namespace,
-        omission::{can_omit_as_before, can_omit_as_last_node},
+        omission::{FirstChild, can_omit_as_before, can_omit_as_last_node, can_omit_opening_tag},
+        whitespace::{WhitespaceMinification, get_whitespace_minification_for_tag},
     },
+    template::contains_template,
 };
 
+// Predicts the first node `minify_content` will write, following its whitespace handling.
+fn first_child<'c>(
+    cfg: &Cfg,
+    ws: &WhitespaceMinification,
+    children: &'c [NodeData],
+) -> FirstChild<'c> {
+    let mut found_first_text_or_elem = false;
+    let mut nodes = children.iter().filter(|c| !is_removed(cfg, c)).peekable();
+    while let Some(c) = nodes.next() {
+        match c {
+            NodeData::Element { name, .. } => return FirstChild::Element(name),
+            NodeData::Text { value, .. } => {
+                // The text around removed nodes is joined.
+                let mut value = value.clone();
+                while let Some(NodeData::Text { value: next, .. }) = nodes.peek() {
+                    value.extend_from_slice(next);
+                    nodes.next();
+                }
+                minify_text_whitespace(ws, !found_first_text_or_elem, &mut value);
+                found_first_text_or_elem = true;
+                match value.first() {
+                    None => {}
+                    Some(c) if c.is_ascii_whitespace() => return FirstChild::Whitespace,
+                    Some(_) => return FirstChild::Text,
+                };
+            }
+            _ => return FirstChild::Comment,
+        };
+    }
+    FirstChild::None
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn minify_element(
     cfg: &Cfg,
-    out: &mut Vec<u8>,
+    out: &mut Output,
+    warnings: &mut Vec<Warning>,
     descendant_of_pre: bool,
     ns: Namespace,
     // Use an empty slice if none.
     parent: &[u8],
+    // Use an empty slice if the previous element or text sibling node is not an element.
+    previous_sibling_as_element_tag_name: &[u8],
     // Use an empty slice if the next element or text sibling node is not an element.
     next_sibling_as_element_tag_name: &[u8],
     // If the last node of the parent is an element and it's this one.
     is_last_child_text_or_element_node: bool,
     tag_name: &[u8],
-    attributes: FxHashMap<Vec<u8>, AttrVal>,
+    attributes: Attributes,
     closing_tag: ElementClosingTag,
+    // Where the closing tag is in the source, if the element was parsed.
+    closing_tag_offset: Option<usize>,
     children: Vec<NodeData>,
 ) {
-    // Output quoted attributes, followed by unquoted, to optimise space omission between attributes.
-    let mut quoted = Vec::new();
-    let mut unquoted = Vec::new();
+    let mut attrs = Vec::with_capacity(attributes.len());
 
     let is_meta_viewport = tag_name == b"meta"
         && attributes
@@ -41,28 +79,68 @@ pub fn minify_element(
             .is_some();
 
     for (name, value) in attributes {
-        match minify_attr(cfg, ns, tag_name, is_meta_viewport, &name, value.value) {
+        let name_span = value.name_span;
+        match minify_attr(cfg, warnings, ns, tag_name, is_meta_viewport, &name, value) {
             AttrMinified::Redundant => {}
-            a @ AttrMinified::NoValue => unquoted.push((name, a)),
-            AttrMinified::Value(v) => {
-                debug_assert!(v.len() > 0);
-                if v.quoted() {
-                    quoted.push((name, v));
-                } else {
-                    unquoted.push((name, AttrMinified::Value(v)));
-                }
+            a => {
+                debug_assert!(!matches!(&a, AttrMinified::Value(v) if v.len() == 0));
+                attrs.push((name, a, name_span));
             }
         };
     }
 
-    // Determinism.
-    quoted.sort_unstable_by(|a, b| a.0.cmp(&b.0));
-    unquoted.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    let is_quoted = |a: &AttrMinified| matches!(a, AttrMinified::Value(v) if v.quoted());
+    // Template tags between attributes must stay where they are.
+    let attr_order = if attrs
+        .iter()
+        .any(|(n, ..)| contains_template(&cfg.template_delimiters, n))
+    {
+        AttrOrder::Source
+    } else {
+        cfg.attr_order
+    };
+    match attr_order {
+        // Output quoted attributes, followed by unquoted, to optimise space omission between attributes.
+        AttrOrder::QuotedFirst => {
+            attrs.sort_unstable_by(|a, b| (!is_quoted(&a.1), &a.0).cmp(&(!is_quoted(&b.1), &b.0)))
+        }
+        AttrOrder::Name => attrs.sort_unstable_by(|a, b| a.0.cmp(&b.0)),
+        // Attributes have already been ordered by `order_by_frequency`.
+        AttrOrder::Frequency | AttrOrder::Source => {}
+    };
+
+    let child_ns = match tag_name {
+        b"svg" => Namespace::Svg,
+        b"math" => Namespace::MathMl,
+        _ => ns,
+    };
+    let child_descendant_of_pre =
+        descendant_of_pre || (ns == Namespace::Html && tag_name == b"pre");
 
     // Attributes list could become empty after minification, so check opening tag omission eligibility after attributes minification.
-    let can_omit_opening_tag = (tag_name == b"html" || tag_name == b"head")
-        && quoted.len() + unquoted.len() == 0
-        && !cfg.keep_html_and_head_opening_tags;
+    let keep_opening_tag = match tag_name {
+        b"html" | b"head" => cfg.keep_html_and_head_opening_tags,
+        _ => cfg.keep_body_colgroup_and_tbody_opening_tags,
+    };
+    let can_omit_opening_tag = ns == Namespace::Html
+        && attrs.is_empty()
+        && !keep_opening_tag
+        && can_omit_opening_tag(
+            tag_name,
+            first_child(
+                cfg,
+                get_whitespace_minification_for_tag(child_ns, tag_name, child_descendant_of_pre),
+                &children,
+            ),
+            // The previous element only matters if its closing tag was omitted.
+            if !cfg.keep_closing_tags
+                && can_omit_as_before(previous_sibling_as_element_tag_name, tag_name)
+            {
+                previous_sibling_as_element_tag_name
+            } else {
+                EMPTY_SLICE
+            },
+        );
     let can_omit_closing_tag = !cfg.keep_closing_tags
         && (can_omit_as_before(tag_name, next_sibling_as_element_tag_name)
             || (is_last_child_text_or_element_node && can_omit_as_last_node(parent, tag_name)));
@@ -71,15 +149,11 @@ pub fn minify_element(
         out.push(b'<');
         out.extend_from_slice(tag_name);
 
-        for (name, value) in &quoted {
-            out.push(b' ');
-            out.extend_from_slice(name);
-            out.push(b'=');
-            debug_assert!(value.quoted());
-            value.out(out);
-        }
-        for (name, value) in &unquoted {
+        for (name, value, name_span) in &attrs {
             out.push(b' ');
+            if !name_span.is_empty() {
+                out.add_mapping(name_span.start);
+            };
             out.extend_from_slice(name);
             if let AttrMinified::Value(v) = value {
                 out.push(b'=');
@@ -89,7 +163,7 @@ pub fn minify_element(
 
         if closing_tag == ElementClosingTag::SelfClosing {
             // Write a space only if the last attribute is unquoted.
-            if !unquoted.is_empty() {
+            if attrs.last().is_some_and(|(_, a, _)| !is_quoted(a)) {
                 out.push(b' ');
             };
             out.push(b'/');
@@ -105,12 +179,9 @@ pub fn minify_element(
     minify_content(
         cfg,
         out,
-        if tag_name == b"svg" {
-            Namespace::Svg
-        } else {
-            ns
-        },
-        descendant_of_pre || (ns == Namespace::Html && tag_name == b"pre"),
+        warnings,
+        child_ns,
+        child_descendant_of_pre,
         tag_name,
         children,
     );
@@ -118,6 +189,9 @@ pub fn minify_element(
     if closing_tag != ElementClosingTag::Present || can_omit_closing_tag {
         return;
     };
+    if let Some(offset) = closing_tag_offset {
+        out.add_mapping(offset);
+    };
     out.extend_from_slice(b"</");
     out.extend_from_slice(tag_name);
     out.push(b'>');
@@ -1,44 +1,252 @@
+#[cfg(feature = "js")]
+use std::path::PathBuf;
+
 #[cfg(feature = "js")]
 use oxc_allocator::Allocator;
 #[cfg(feature = "js")]
-use oxc_codegen::{Codegen, CodegenOptions};
+use oxc_codegen::{Codegen, CodegenOptions, CodegenReturn};
 #[cfg(feature = "js")]
-use oxc_mangler::MangleOptions;
+use oxc_mangler::{MangleOptions, MangleOptionsKeepNames};
 #[cfg(feature = "js")]
-use oxc_minifier::{CompressOptions, Minifier, MinifierOptions};
+use oxc_minifier::{CompressOptions, CompressOptionsKeepNames, Minifier, MinifierOptions};
 #[cfg(feature = "js")]
-use oxc_parser::Parser;
+use oxc_parser::{ParseOptions, Parser};
 #[cfg(feature = "js")]
 use oxc_span::SourceType;
+#[cfg(feature = "js")]
+use oxc_syntax::es_target::ESTarget;
 
-use crate::whitespace::trimmed;
+use crate::{cfg::Cfg, minify::output::Output, report::Warning, whitespace::trimmed};
+#[cfg(feature = "js")]
+use crate::{
+    cfg::{EsTarget, JsOptions},
+    minify::utf8::decode_utf8,
+    report::WarningKind,
+    source_map::add_inner_mappings,
+};
 
 #[cfg(feature = "js")]
-pub fn minify_js(out: &mut Vec<u8>, code: &[u8]) {
+pub fn minify_js(
+    cfg: &Cfg,
+    out: &mut Output,
+    warnings: &mut Vec<Warning>,
+    code: &[u8],
+    offset: usize,
+    module: bool,
+) {
     let allocator = Allocator::default();
-    let source_type = SourceType::cjs();
-    let code = std::str::from_utf8(code).expect("js contained invalid utf-8");
-    let minified = minify(&allocator, code, source_type);
-    out.extend_from_slice(trimmed(minified.as_bytes()));
+    let source_type = if module {
+        SourceType::mjs()
+    } else {
+        SourceType::cjs()
+    };
+    let Some(src) = decode_utf8(cfg, warnings, code, offset) else {
+        out.extend_from_slice(trimmed(code));
+        return;
+    };
+    // The map is only useful if offsets into the decoded code are offsets into the source.
+    let source_map = out.is_mapped() && src.len() == code.len();
+    match minify(
+        &allocator,
+        &cfg.js,
+        &src,
+        source_type,
+        false,
+        true,
+        source_map,
+    ) {
+        Ok(CodegenReturn {
+            code: minified,
+            map,
+            ..
+        }) => {
+            let min = trimmed(minified.as_bytes());
+            if let Some(map) = map {
+                let skip = minified.len() - minified.trim_start().len();
+                let tokens = map.get_tokens().map(|t| {
+                    [
+                        t.get_dst_line(),
+                        t.get_dst_col(),
+                        t.get_src_line(),
+                        t.get_src_col(),
+                    ]
+                });
+                add_inner_mappings(out, &minified, skip, &src, offset, true, tokens);
+            };
+            out.extend_from_slice(min);
+        }
+        Err((error_offset, message)) => {
+            warnings.push(Warning {
+                kind: WarningKind::InvalidJs,
+                offset: offset + error_offset,
+                message,
+            });
+            out.extend_from_slice(trimmed(code));
+        }
+    };
 }
 
 #[cfg(feature = "js")]
-fn minify(allocator: &Allocator, source: &str, source_type: SourceType) -> String {
-    let ret = Parser::new(allocator, source, source_type).parse();
+fn es_target(target: EsTarget) -> ESTarget {
+    match target {
+        EsTarget::Es5 => ESTarget::ES5,
+        EsTarget::Es2015 => ESTarget::ES2015,
+        EsTarget::Es2016 => ESTarget::ES2016,
+        EsTarget::Es2017 => ESTarget::ES2017,
+        EsTarget::Es2018 => ESTarget::ES2018,
+        EsTarget::Es2019 => ESTarget::ES2019,
+        EsTarget::Es2020 => ESTarget::ES2020,
+        EsTarget::Es2021 => ESTarget::ES2021,
+        EsTarget::Es2022 => ESTarget::ES2022,
+        EsTarget::Es2023 => ESTarget::ES2023,
+        EsTarget::Es2024 => ESTarget::ES2024,
+        EsTarget::Es2025 => ESTarget::ES2025,
+        EsTarget::EsNext => ESTarget::ESNext,
+    }
+}
+
+// Returns the offset and message of the first parse error if the source is not valid. If
+// `function_body` is true, the source is parsed as the body of a function, as for event handlers.
+// Without `compress`, only whitespace is removed and names are shortened. If `source_map` is true, the
+// result has a map from the minified code back to the source.
+#[cfg(feature = "js")]
+fn minify(
+    allocator: &Allocator,
+    opts: &JsOptions,
+    source: &str,
+    source_type: SourceType,
+    function_body: bool,
+    compress: bool,
+    source_map: bool,
+) -> Result<CodegenReturn, (usize, String)> {
+    let ret = Parser::new(allocator, source, source_type)
+        .with_options(ParseOptions {
+            allow_return_outside_function: function_body,
+            ..ParseOptions::default()
+        })
+        .parse();
+    if let Some(err) = ret.errors.first() {
+        let offset = err
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.first())
+            .map_or(0, |label| label.offset());
+        return Err((offset, err.to_string()));
+    };
     let mut program = ret.program;
     let options = MinifierOptions {
-        mangle: Some(MangleOptions::default()),
-        compress: Some(CompressOptions::default()),
+        mangle: opts.mangle.then(|| MangleOptions {
+            top_level: opts.mangle_top_level,
+            keep_names: MangleOptionsKeepNames {
+                function: opts.keep_fn_names,
+                class: opts.keep_fn_names,
+            },
+            ..MangleOptions::default()
+        }),
+        compress: compress.then(|| CompressOptions {
+            target: es_target(opts.target),
+            drop_console: opts.drop_console,
+            drop_debugger: opts.drop_debugger,
+            keep_names: CompressOptionsKeepNames {
+                function: opts.keep_fn_names,
+                class: opts.keep_fn_names,
+            },
+            ..CompressOptions::default()
+        }),
     };
     let ret = Minifier::new(options).build(allocator, &mut program);
-    Codegen::new()
-        .with_options(CodegenOptions::minify())
+    Ok(Codegen::new()
+        .with_options(CodegenOptions {
+            // Only used as the name of the source in the map, which isn't kept.
+            source_map_path: source_map.then(|| PathBuf::from("script.js")),
+            ..CodegenOptions::minify()
+        })
         .with_scoping(ret.scoping)
-        .build(&program)
-        .code
+        .build(&program))
+}
+
+// Minifies attribute code in place, leaving it as is if it can't be parsed or doesn't get any
+// shorter.
+#[cfg(feature = "js")]
+fn minify_attr_code(
+    cfg: &Cfg,
+    warnings: &mut Vec<Warning>,
+    value: &mut Vec<u8>,
+    offset: usize,
+    is_url: bool,
+) {
+    let Some(src) = decode_utf8(cfg, warnings, value, offset) else {
+        return;
+    };
+    let allocator = Allocator::default();
+    // The value of the last expression of a `javascript:` URL replaces the page if it's a string, so
+    // compression, which drops unused expressions, can't be used.
+    match minify(
+        &allocator,
+        &cfg.js,
+        &src,
+        SourceType::cjs(),
+        !is_url,
+        !is_url,
+        false,
+    ) {
+        Ok(minified) => {
+            let minified = trimmed(minified.code.as_bytes());
+            if minified.len() < value.len() {
+                *value = minified.to_vec();
+            };
+        }
+        Err((error_offset, message)) => warnings.push(Warning {
+            kind: WarningKind::InvalidJs,
+            offset: offset + error_offset,
+            message,
+        }),
+    };
+}
+
+#[cfg(not(feature = "js"))]
+fn minify_attr_code(
+    _cfg: &Cfg,
+    _warnings: &mut Vec<Warning>,
+    _value: &mut Vec<u8>,
+    _offset: usize,
+    _is_url: bool,
+) {
+}
+
+// Minifies event handler attribute code in place, such as the value of `onclick`.
+pub fn minify_js_attr(cfg: &Cfg, warnings: &mut Vec<Warning>, value: &mut Vec<u8>, offset: usize) {
+    minify_attr_code(cfg, warnings, value, offset, false);
+}
+
+// Minifies the code of a `javascript:` URL in place like `minify_js_attr`. URLs with
+// percent-encoded characters are left as is, as browsers decode them before running the code.
+pub fn minify_javascript_url(
+    cfg: &Cfg,
+    warnings: &mut Vec<Warning>,
+    value: &mut Vec<u8>,
+    offset: usize,
+) {
+    const SCHEME: &[u8] = b"javascript:";
+    if value.len() <= SCHEME.len()
+        || !value[..SCHEME.len()].eq_ignore_ascii_case(SCHEME)
+        || value.contains(&b'%')
+    {
+        return;
+    };
+    let mut code = value.split_off(SCHEME.len());
+    minify_attr_code(cfg, warnings, &mut code, offset + SCHEME.len(), true);
+    value.extend_from_slice(&code);
 }
 
 #[cfg(not(feature = "js"))]
-pub fn minify_js(out: &mut Vec<u8>, code: &[u8]) {
+pub fn minify_js(
+    _cfg: &Cfg,
+    out: &mut Output,
+    _warnings: &mut Vec<Warning>,
+    code: &[u8],
+    _offset: usize,
+    _module: bool,
+) {
     out.extend_from_slice(trimmed(code));
 }
@@ -0,0 +1,193 @@
+use crate::{
+    code_gen::codepoints::{DIGIT, HEX_DIGIT},
+    report::{Warning, WarningKind},
+};
+
+// Unlike HTML, JSON doesn't treat form feeds as whitespace.
+fn is_json_whitespace(c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+// A minimal JSON parser that copies the source to `out` without insignificant whitespace.
+struct JsonMinifier<'c> {
+    code: &'c [u8],
+    next: usize,
+    out: Vec<u8>,
+}
+
+impl JsonMinifier<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.code.get(self.next).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(is_json_whitespace) {
+            self.next += 1;
+        }
+    }
+
+    fn copy_if_next(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.out.push(c);
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn copy_digits(&mut self) -> usize {
+        let start = self.next;
+        while self.peek().filter(|&c| DIGIT[c]).is_some() {
+            self.next += 1;
+        }
+        self.out.extend_from_slice(&self.code[start..self.next]);
+        self.next - start
+    }
+
+    // Parses an object key and the `:` after it.
+    fn key(&mut self) -> Option<()> {
+        self.skip_whitespace();
+        if self.peek()? != b'"' {
+            return None;
+        };
+        self.string()?;
+        self.skip_whitespace();
+        self.copy_if_next(b':').then_some(())
+    }
+
+    fn value(&mut self) -> Option<()> {
+        // Whether each open container is an object. They're kept here instead of on the call stack so
+        // that deeply nested input can't overflow it.
+        let mut open = Vec::<bool>::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek()? {
+                c @ (b'{' | b'[') => {
+                    let is_object = c == b'{';
+                    self.copy_if_next(c);
+                    self.skip_whitespace();
+                    if !self.copy_if_next(if is_object { b'}' } else { b']' }) {
+                        open.push(is_object);
+                        if is_object {
+                            self.key()?;
+                        };
+                        continue;
+                    };
+                }
+                b'"' => self.string()?,
+                b'-' | b'0'..=b'9' => self.number()?,
+                _ => [b"true".as_slice(), b"false", b"null"]
+                    .into_iter()
+                    .find(|lit| self.code[self.next..].starts_with(lit))
+                    .map(|lit| {
+                        self.out.extend_from_slice(lit);
+                        self.next += lit.len();
+                    })?,
+            };
+            // A value has ended, so close the containers that end with it, then start the next one.
+            loop {
+                self.skip_whitespace();
+                let Some(&is_object) = open.last() else {
+                    return Some(());
+                };
+                if self.copy_if_next(if is_object { b'}' } else { b']' }) {
+                    open.pop();
+                    continue;
+                };
+                if !self.copy_if_next(b',') {
+                    return None;
+                };
+                if is_object {
+                    self.key()?;
+                };
+                break;
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<()> {
+        self.copy_if_next(b'"');
+        loop {
+            let c = self.peek()?;
+            self.next += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let e = self.peek()?;
+                    self.next += 1;
+                    self.out.push(b'\\');
+                    self.out.push(e);
+                    match e {
+                        b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => {}
+                        b'u' => {
+                            let hex = self.code.get(self.next..self.next + 4)?;
+                            if !hex.iter().all(|&h| HEX_DIGIT[h]) {
+                                return None;
+                            };
+                            self.out.extend_from_slice(hex);
+                            self.next += 4;
+                        }
+                        _ => return None,
+                    };
+                }
+                c if c < 0x20 => return None,
+                // Prevent the string from closing the script or opening an escaped script data state.
+                b'<' if self.code[self.next..].starts_with(b"!--") => {
+                    self.out.extend_from_slice(b"\\u003c");
+                }
+                b'<' if self
+                    .code
+                    .get(self.next..self.next + 7)
+                    .filter(|s| s.eq_ignore_ascii_case(b"/script"))
+                    .is_some() =>
+                {
+                    self.out.extend_from_slice(b"<\\");
+                }
+                c => self.out.push(c),
+            };
+        }
+        self.out.push(b'"');
+        Some(())
+    }
+
+    fn number(&mut self) -> Option<()> {
+        self.copy_if_next(b'-');
+        if !self.copy_if_next(b'0') && self.copy_digits() == 0 {
+            return None;
+        };
+        if self.copy_if_next(b'.') && self.copy_digits() == 0 {
+            return None;
+        };
+        if self.copy_if_next(b'e') || self.copy_if_next(b'E') {
+            let _ = self.copy_if_next(b'+') || self.copy_if_next(b'-');
+            if self.copy_digits() == 0 {
+                return None;
+            };
+        };
+        Some(())
+    }
+}
+
+pub fn minify_json(out: &mut Vec<u8>, warnings: &mut Vec<Warning>, code: &[u8], offset: usize) {
+    // An empty script isn't invalid, it just has no data.
+    if code.iter().all(|&c| is_json_whitespace(c)) {
+        return;
+    };
+    let mut m = JsonMinifier {
+        code,
+        next: 0,
+        out: Vec::with_capacity(code.len()),
+    };
+    match m.value() {
+        Some(()) if m.next == code.len() => out.extend_from_slice(&m.out),
+        _ => {
+            warnings.push(Warning {
+                kind: WarningKind::InvalidJson,
+                offset: offset + m.next,
+                message: "invalid JSON".to_string(),
+            });
+            out.extend_from_slice(code);
+        }
+    };
+}
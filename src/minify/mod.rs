@@ -7,6 +7,12 @@ pub mod doctype;
 pub mod element;
 pub mod instruction;
 pub mod js;
+pub mod json;
+pub mod order;
+pub mod output;
 pub mod rcdata;
+pub mod svg;
 #[cfg(test)]
 mod tests;
+#[cfg(any(feature = "css", feature = "js"))]
+pub mod utf8;
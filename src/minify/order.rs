@@ -0,0 +1,74 @@
+use rustc_hash::FxHashMap;
+
+use crate::{ast::NodeData, cfg::Cfg, template::contains_template};
+
+#[derive(Default)]
+struct Frequencies {
+    attrs: FxHashMap<Vec<u8>, usize>,
+    classes: FxHashMap<Vec<u8>, usize>,
+}
+
+fn class_tokens(value: &[u8]) -> impl Iterator<Item = &[u8]> {
+    value
+        .split(u8::is_ascii_whitespace)
+        .filter(|t| !t.is_empty())
+}
+
+fn count(freq: &mut Frequencies, nodes: &[NodeData]) {
+    for n in nodes {
+        if let NodeData::Element {
+            attributes,
+            children,
+            ..
+        } = n
+        {
+            for (name, value) in attributes {
+                *freq.attrs.entry(name.clone()).or_default() += 1;
+                if name == b"class" {
+                    for t in class_tokens(&value.value) {
+                        *freq.classes.entry(t.to_vec()).or_default() += 1;
+                    }
+                };
+            }
+            count(freq, children);
+        };
+    }
+}
+
+fn reorder(cfg: &Cfg, freq: &Frequencies, nodes: &mut [NodeData]) {
+    let delims = cfg.template_delimiters.as_slice();
+    for n in nodes {
+        if let NodeData::Element {
+            attributes,
+            children,
+            ..
+        } = n
+        {
+            // Most frequent first, so that runs of the same attributes and classes repeat throughout the
+            // document; ties are broken by name so the order is stable. Template tags between
+            // attributes must stay where they are.
+            if !attributes.keys().any(|n| contains_template(delims, n)) {
+                attributes.sort_unstable_by(|a, _, b, _| {
+                    freq.attrs[b].cmp(&freq.attrs[a]).then(a.cmp(b))
+                });
+            };
+            if let Some(class) = attributes
+                .get_mut(b"class".as_slice())
+                .filter(|c| !contains_template(delims, &c.value))
+            {
+                let mut tokens = class_tokens(&class.value).collect::<Vec<_>>();
+                tokens.sort_by(|a, b| freq.classes[*b].cmp(&freq.classes[*a]).then(a.cmp(b)));
+                class.value = tokens.join(b" ".as_slice());
+            };
+            reorder(cfg, freq, children);
+        };
+    }
+}
+
+// Orders attributes and class names of every element by how often they occur in the document, for
+// `AttrOrder::Frequency`.
+pub fn order_by_frequency(cfg: &Cfg, nodes: &mut [NodeData]) {
+    let mut freq = Frequencies::default();
+    count(&mut freq, nodes);
+    reorder(cfg, &freq, nodes);
+}
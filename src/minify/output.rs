@@ -0,0 +1,147 @@
+use std::{
+    io::{self, Write},
+    ops::{Deref, DerefMut},
+};
+
+use crate::source_map::Mapping;
+
+// Output is written out whenever this much has been buffered between nodes.
+const FLUSH_THRESHOLD: usize = 64 * 1024;
+
+// The buffer minified code is written to. If it has a writer, the buffer is written out in chunks as
+// minification progresses, so the whole output never needs to be held in memory.
+pub struct Output<'w> {
+    buf: Vec<u8>,
+    writer: Option<&'w mut dyn Write>,
+    // The first write error, after which the rest of the output is discarded.
+    error: Option<io::Error>,
+    // How much of the output has been written out and is no longer in the buffer.
+    written: usize,
+    // Points in the output that map back to the source, ordered by their offset in the output. None if
+    // no source map is wanted.
+    mappings: Option<Vec<Mapping>>,
+}
+
+impl<'w> Output<'w> {
+    pub fn buffered(capacity: usize) -> Output<'w> {
+        Output {
+            buf: Vec::with_capacity(capacity),
+            writer: None,
+            error: None,
+            written: 0,
+            mappings: None,
+        }
+    }
+
+    pub fn streaming(writer: &'w mut dyn Write) -> Output<'w> {
+        Output {
+            buf: Vec::with_capacity(FLUSH_THRESHOLD),
+            writer: Some(writer),
+            error: None,
+            written: 0,
+            mappings: None,
+        }
+    }
+
+    // Records mappings back to the source as output is written. See `add_mapping`.
+    pub fn with_source_map(mut self) -> Output<'w> {
+        self.mappings = Some(Vec::new());
+        self
+    }
+
+    #[cfg(any(feature = "css", feature = "js"))]
+    pub const fn is_mapped(&self) -> bool {
+        self.mappings.is_some()
+    }
+
+    // Offset in the whole output of the next byte written, including what's already been written out.
+    pub fn position(&self) -> usize {
+        self.written + self.buf.len()
+    }
+
+    // Maps the next byte written to `original` in the source.
+    pub fn add_mapping(&mut self, original: usize) {
+        self.add_mapping_at(self.position(), original);
+    }
+
+    // Maps output at `generated`, which must not come before the position of the last mapping, to
+    // `original` in the source. A later mapping at the same position replaces the earlier one, as
+    // nothing was written for it.
+    pub fn add_mapping_at(&mut self, generated: usize, original: usize) {
+        let Some(mappings) = self.mappings.as_mut() else {
+            return;
+        };
+        match mappings.last_mut() {
+            Some(last) if last.generated > generated => {}
+            Some(last) if last.generated == generated => last.original = original,
+            _ => mappings.push(Mapping {
+                generated,
+                original,
+            }),
+        };
+    }
+
+    // Removes a mapping added at `generated` if nothing has been written for it since, so that it
+    // doesn't claim whatever is written next.
+    pub fn drop_unused_mapping(&mut self, generated: usize) {
+        if self.position() == generated
+            && let Some(mappings) = self.mappings.as_mut()
+            && mappings.last().is_some_and(|m| m.generated == generated)
+        {
+            mappings.pop();
+        };
+    }
+
+    pub fn take_mappings(&mut self) -> Vec<Mapping> {
+        self.mappings.take().unwrap_or_default()
+    }
+
+    fn write_out(&mut self) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        if self.error.is_none()
+            && let Err(err) = writer.write_all(&self.buf)
+        {
+            self.error = Some(err);
+        };
+        self.written += self.buf.len();
+        self.buf.clear();
+    }
+
+    // Must only be called at a point where the output so far is final, such as between nodes.
+    pub fn maybe_flush(&mut self) {
+        if self.buf.len() >= FLUSH_THRESHOLD {
+            self.write_out();
+        };
+    }
+
+    // Returns the output if there's no writer, otherwise writes out the rest of the buffer and
+    // returns an empty vector.
+    pub fn finish(mut self) -> io::Result<Vec<u8>> {
+        if self.writer.is_none() {
+            self.buf.shrink_to_fit();
+            return Ok(self.buf);
+        };
+        self.write_out();
+        if let Some(err) = self.error {
+            return Err(err);
+        };
+        self.writer.unwrap().flush()?;
+        Ok(Vec::new())
+    }
+}
+
+impl Deref for Output<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buf
+    }
+}
+
+impl DerefMut for Output<'_> {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+}
@@ -1,12 +1,21 @@
 use crate::{
     ast::RcdataContentType,
+    cfg::Cfg,
     entity::encode::encode_entities,
     tag::{TAG_TEXTAREA_END, TAG_TITLE_END},
+    template::split_templates,
 };
 
-pub fn minify_rcdata(out: &mut Vec<u8>, typ: RcdataContentType, text: &[u8]) {
-    // Encode entities, since they're still decoded by the browser.
-    let html = encode_entities(text, false);
+pub fn minify_rcdata(cfg: &Cfg, out: &mut Vec<u8>, typ: RcdataContentType, text: &[u8]) {
+    // Encode entities, since they're still decoded by the browser. Template tags are left as they are.
+    let mut html = Vec::with_capacity(text.len());
+    for (is_template, s) in split_templates(&cfg.template_delimiters, text) {
+        if is_template {
+            html.extend_from_slice(s);
+        } else {
+            html.extend_from_slice(&encode_entities(s, false));
+        };
+    }
 
     // Since the text has been decoded, there may be unintentional matches to end tags that we must escape.
     let html = match typ {
@@ -0,0 +1,193 @@
+use crate::{cfg::Cfg, minify::css::minify_presentation_attr, report::Warning};
+
+// A number or command of SVG path data, or of a `points` or `viewBox` list of numbers.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Token<'a> {
+    Command(u8),
+    // The large-arc and sweep flags of arc commands, which are a single `0` or `1`.
+    Flag(u8),
+    Number(&'a [u8]),
+}
+
+// Number of parameters of each path command.
+fn command_params(cmd: u8) -> Option<usize> {
+    Some(match cmd.to_ascii_lowercase() {
+        b'z' => 0,
+        b'h' | b'v' => 1,
+        b'm' | b'l' | b't' => 2,
+        b's' | b'q' => 4,
+        b'c' => 6,
+        b'a' => 7,
+        _ => return None,
+    })
+}
+
+// Whether a command has been given all of its parameters, perhaps more than once.
+fn is_complete(cmd: Option<u8>, param: usize) -> bool {
+    match cmd.map(|c| command_params(c).unwrap()) {
+        None | Some(0) => param == 0,
+        Some(n) => param > 0 && param.is_multiple_of(n),
+    }
+}
+
+fn is_separator(c: u8) -> bool {
+    c.is_ascii_whitespace() || c == b','
+}
+
+// Length of the number at the start of `src`, following the SVG number grammar.
+fn number_len(src: &[u8]) -> Option<usize> {
+    let digits = |i: usize| src[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+    let mut i = usize::from(matches!(src.first(), Some(b'+' | b'-')));
+    let int = digits(i);
+    i += int;
+    let mut frac = 0;
+    if src.get(i) == Some(&b'.') {
+        frac = digits(i + 1);
+        if frac > 0 {
+            i += 1 + frac;
+        };
+    };
+    if int == 0 && frac == 0 {
+        return None;
+    };
+    if matches!(src.get(i), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(src.get(i + 1), Some(b'+' | b'-')));
+        let exp = digits(i + 1 + sign);
+        if exp > 0 {
+            i += 1 + sign + exp;
+        };
+    };
+    Some(i)
+}
+
+fn tokenize(src: &[u8], commands: bool) -> Option<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    // The current command, and how many of its parameters have been read.
+    let mut cmd = None;
+    let mut param = 0;
+    loop {
+        while src.get(i).is_some_and(|&c| is_separator(c)) {
+            i += 1;
+        }
+        let Some(&c) = src.get(i) else {
+            break;
+        };
+        if commands && c.is_ascii_alphabetic() && !matches!(c, b'e' | b'E') {
+            command_params(c)?;
+            if cmd.is_some() && !is_complete(cmd, param) {
+                return None;
+            };
+            tokens.push(Token::Command(c));
+            cmd = Some(c.to_ascii_lowercase());
+            param = 0;
+            i += 1;
+            continue;
+        };
+        if commands && cmd.is_none() {
+            return None;
+        };
+        let params = cmd.map_or(1, |c| command_params(c).unwrap());
+        if params == 0 {
+            return None;
+        };
+        if cmd == Some(b'a') && matches!(param % 7, 3 | 4) {
+            if !matches!(c, b'0' | b'1') {
+                return None;
+            };
+            tokens.push(Token::Flag(c));
+            i += 1;
+        } else {
+            let len = number_len(&src[i..])?;
+            tokens.push(Token::Number(&src[i..i + len]));
+            i += len;
+        };
+        param += 1;
+    }
+    (!commands || is_complete(cmd, param)).then_some(tokens)
+}
+
+// Shortens a number by dropping its `+` sign and redundant zeros, e.g. `+0.50` becomes `.5`.
+fn minify_number(num: &[u8], out: &mut Vec<u8>) {
+    let (negative, num) = match num.first() {
+        Some(b'-') => (true, &num[1..]),
+        Some(b'+') => (false, &num[1..]),
+        _ => (false, num),
+    };
+    let (mantissa, exponent) = match num.iter().position(|&c| c == b'e' || c == b'E') {
+        Some(e) => num.split_at(e),
+        None => (num, &[][..]),
+    };
+    let (int, frac) = match mantissa.iter().position(|&c| c == b'.') {
+        Some(dot) => (&mantissa[..dot], &mantissa[dot + 1..]),
+        None => (mantissa, &[][..]),
+    };
+    let int = &int[int.iter().take_while(|&&c| c == b'0').count()..];
+    let frac = &frac[..frac.len() - frac.iter().rev().take_while(|&&c| c == b'0').count()];
+    if int.is_empty() && frac.is_empty() {
+        // Zero has no sign or exponent worth keeping.
+        out.push(b'0');
+        return;
+    };
+    if negative {
+        out.push(b'-');
+    };
+    out.extend_from_slice(int);
+    if !frac.is_empty() {
+        out.push(b'.');
+        out.extend_from_slice(frac);
+    };
+    out.extend_from_slice(exponent);
+}
+
+// Minifies SVG path data if `commands` is true, or otherwise a list of numbers like `points` and
+// `viewBox`, by shortening numbers and only keeping the separators that are needed. Returns None if
+// the value isn't valid, so it can be left as is.
+pub fn minify_svg_numbers(src: &[u8], commands: bool) -> Option<Vec<u8>> {
+    let tokens = tokenize(src, commands)?;
+    let mut out = Vec::with_capacity(src.len());
+    let mut prev = None;
+    for t in tokens {
+        let start = out.len();
+        match t {
+            Token::Command(c) => out.push(c),
+            Token::Flag(c) => out.push(c),
+            Token::Number(n) => minify_number(n, &mut out),
+        };
+        let next = out[start];
+        // A `-` always starts a new number, and so does a `.` after a number that already has one.
+        let needs_separator = match prev {
+            None | Some(Token::Command(_)) => false,
+            Some(_) if next == b'-' => false,
+            Some(Token::Number(_)) if next == b'.' => {
+                let p = &out[..start];
+                let p = &p[p.iter().rposition(|c| !c.is_ascii_digit()).unwrap_or(0)..];
+                p.first() != Some(&b'.')
+            }
+            Some(_) => !matches!(t, Token::Command(_)),
+        };
+        if needs_separator {
+            out.insert(start, b' ');
+        };
+        prev = Some(t);
+    }
+    Some(out)
+}
+
+// Minifies the value of an SVG attribute that has CSS or numeric syntax, leaving it as is otherwise.
+pub fn minify_svg_attr(
+    cfg: &Cfg,
+    warnings: &mut Vec<Warning>,
+    name: &[u8],
+    value: &mut Vec<u8>,
+    offset: usize,
+) {
+    match name {
+        b"d" | b"points" | b"viewbox" if cfg.minify_svg_path_data => {
+            if let Some(min) = minify_svg_numbers(value, name == b"d") {
+                *value = min;
+            };
+        }
+        _ => minify_presentation_attr(cfg, warnings, name, value, offset),
+    };
+}
@@ -0,0 +1,43 @@
+use crate::minify::svg::minify_svg_numbers;
+
+fn path(src: &[u8]) -> String {
+    String::from_utf8(minify_svg_numbers(src, true).unwrap()).unwrap()
+}
+
+#[test]
+fn test_minify_svg_path_data() {
+    assert_eq!(path(b"M 0.50,10.0 L 1 , -1 z"), "M.5 10L1-1z");
+    assert_eq!(
+        path(b"M0 0 l 0.5 0.25 +3.0e2 -0.0 h 001.10"),
+        "M0 0l.5.25 3e2 0h1.1"
+    );
+    assert_eq!(
+        path(b"m1.5.5 c 1, 2, 3, 4, 5, 6 7 8 9 10 11 12"),
+        "m1.5.5c1 2 3 4 5 6 7 8 9 10 11 12"
+    );
+    // Arc flags are a single digit, and may be written without separators.
+    assert_eq!(
+        path(b"M0,0 a 5 5 0 1 0 .5 .5 A5,5,30,01-1,-1"),
+        "M0 0a5 5 0 1 0 .5.5A5 5 30 0 1-1-1"
+    );
+    for invalid in [
+        &b"0 0"[..],
+        b"M0",
+        b"M0 0 x1",
+        b"M1 2 a 1 1 0 2 0 1 1",
+        b"M0 0 z 1",
+    ] {
+        assert!(
+            minify_svg_numbers(invalid, true).is_none(),
+            "{}",
+            String::from_utf8_lossy(invalid)
+        );
+    }
+}
+
+#[test]
+fn test_minify_svg_number_list() {
+    let min = minify_svg_numbers(b" 0, 0, 100.0, -0.5 ", false).unwrap();
+    assert_eq!(min, b"0 0 100-.5");
+    assert!(minify_svg_numbers(b"0 0 M", false).is_none());
+}
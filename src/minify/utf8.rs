@@ -0,0 +1,32 @@
+use std::{borrow::Cow, str::from_utf8};
+
+use crate::{
+    Cfg,
+    report::{Warning, WarningKind},
+};
+
+// Returns None if the code is not valid UTF-8 and should be left as is.
+pub fn decode_utf8<'c>(
+    cfg: &Cfg,
+    warnings: &mut Vec<Warning>,
+    code: &'c [u8],
+    offset: usize,
+) -> Option<Cow<'c, str>> {
+    match from_utf8(code) {
+        Ok(src) => Some(Cow::Borrowed(src)),
+        Err(err) => {
+            let lossy = cfg.decode_invalid_utf8_lossily;
+            warnings.push(Warning {
+                kind: WarningKind::InvalidUtf8,
+                offset: offset + err.valid_up_to(),
+                message: if lossy {
+                    "replaced invalid UTF-8 with U+FFFD"
+                } else {
+                    "left code containing invalid UTF-8 as is"
+                }
+                .to_string(),
+            });
+            lossy.then(|| String::from_utf8_lossy(code))
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use memchr::memchr;
 
-use crate::{ast::NodeData, parse::Code};
+use crate::{ast::NodeData, parse::Code, report::WarningKind};
 
 pub fn parse_bang(code: &mut Code) -> NodeData {
     debug_assert!(code.as_slice().starts_with(b"<!"));
+    let start = code.offset();
     code.shift(2);
     let (len, matched) = match memchr(b'>', code.as_slice()) {
         Some(m) => (m, 1),
@@ -12,8 +13,12 @@ pub fn parse_bang(code: &mut Code) -> NodeData {
     let data = code.copy_and_shift(len);
     // It might be EOF.
     code.shift(matched);
+    if matched == 0 {
+        code.warn_at(start, WarningKind::UnclosedBang, "bang is not closed");
+    };
     NodeData::Bang {
         code: data,
         ended: matched > 0,
+        span: code.span_from(start),
     }
 }
@@ -2,7 +2,7 @@ use std::sync::LazyLock;
 
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, AhoCorasickKind};
 
-use crate::{ast::NodeData, parse::Code};
+use crate::{ast::NodeData, parse::Code, report::WarningKind};
 
 static COMMENT_END: LazyLock<AhoCorasick> = LazyLock::new(|| {
     AhoCorasickBuilder::new()
@@ -13,6 +13,7 @@ static COMMENT_END: LazyLock<AhoCorasick> = LazyLock::new(|| {
 
 pub fn parse_comment(code: &mut Code) -> NodeData {
     debug_assert!(code.as_slice().starts_with(b"<!--"));
+    let start = code.offset();
     code.shift(4);
     let (len, matched) = match COMMENT_END.find(code.as_slice()) {
         Some(m) => (m.start(), m.end() - m.start()),
@@ -21,8 +22,12 @@ pub fn parse_comment(code: &mut Code) -> NodeData {
     let data = code.copy_and_shift(len);
     // It might be EOF.
     code.shift(matched);
+    if matched == 0 {
+        code.warn_at(start, WarningKind::UnclosedComment, "comment is not closed");
+    };
     NodeData::Comment {
         code: data,
         ended: matched > 0,
+        span: code.span_from(start),
     }
 }
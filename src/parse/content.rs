@@ -3,7 +3,7 @@ use std::sync::LazyLock;
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, AhoCorasickKind, MatchKind};
 
 use crate::{
-    ast::NodeData,
+    ast::{ElementClosingTag, NodeData, Span},
     code_gen::codepoints::TAG_NAME_CHAR,
     entity::decode::decode_entities,
     parse::{
@@ -14,18 +14,23 @@ use crate::{
         doctype::parse_doctype,
         element::{parse_element, parse_tag, peek_tag_name},
         instruction::parse_instruction,
+        verbatim::{find_template_in_text, is_minify_off, parse_template, parse_verbatim},
     },
+    report::WarningKind,
     spec::tag::{
         ns::Namespace,
         omission::{can_omit_as_before, can_omit_as_last_node},
         void::VOID_TAGS,
     },
+    whitespace::is_all_whitespace,
 };
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum ContentType {
     Bang,
     ClosingTag,
+    // A `</colgroup>` that closes the `<colgroup>` browsers imply around `<col>` elements in a `<table>`.
+    ClosingImpliedColgroupTag,
     Comment,
     Doctype,
     IgnoredTag,
@@ -33,9 +38,33 @@ enum ContentType {
     MalformedLeftChevronSlash,
     OmittedClosingTag,
     OpeningTag,
+    Template,
     Text,
 }
 
+// Where the `<colgroup>` that browsers imply around `<col>` elements in a `<table>` starts, if one is
+// still open at the end of `nodes`. Whitespace and comments after a `<col>` are put in it too.
+fn implied_colgroup_start(nodes: &[NodeData]) -> Option<usize> {
+    let mut start = None;
+    for (i, n) in nodes.iter().enumerate().rev() {
+        match n {
+            NodeData::Element { name, .. } if name == b"col" => start = Some(i),
+            NodeData::Text { value, .. } if is_all_whitespace(value) => {}
+            NodeData::Bang { .. } | NodeData::Comment { .. } | NodeData::Instruction { .. } => {}
+            _ => break,
+        };
+    }
+    start
+}
+
+fn ignore_duplicate(code: &mut Code, name: &[u8]) -> ContentType {
+    code.warn(
+        WarningKind::IgnoredDuplicateTag,
+        format!("ignored duplicate `{}` tag", String::from_utf8_lossy(name)),
+    );
+    IgnoredTag
+}
+
 fn maybe_ignore_html_head_body(
     code: &mut Code,
     typ: ContentType,
@@ -45,7 +74,7 @@ fn maybe_ignore_html_head_body(
     match (typ, name, parent) {
         (OpeningTag, b"html", _) => {
             if code.seen_html_open {
-                IgnoredTag
+                ignore_duplicate(code, name)
             } else {
                 code.seen_html_open = true;
                 typ
@@ -53,7 +82,7 @@ fn maybe_ignore_html_head_body(
         }
         (OpeningTag, b"head", _) => {
             if code.seen_head_open {
-                IgnoredTag
+                ignore_duplicate(code, name)
             } else {
                 code.seen_head_open = true;
                 typ
@@ -61,7 +90,7 @@ fn maybe_ignore_html_head_body(
         }
         (ClosingTag, b"head", _) => {
             if code.seen_head_close {
-                IgnoredTag
+                ignore_duplicate(code, name)
             } else {
                 code.seen_head_close = true;
                 typ
@@ -73,7 +102,7 @@ fn maybe_ignore_html_head_body(
         }
         (OpeningTag, b"body", _) => {
             if code.seen_body_open {
-                IgnoredTag
+                ignore_duplicate(code, name)
             } else {
                 code.seen_body_open = true;
                 typ
@@ -143,17 +172,28 @@ pub fn parse_content(
     let mut nodes = Vec::<NodeData>::new();
     let matcher = &CONTENT_TYPE_MATCHER;
     loop {
-        let (text_len, mut typ) = match matcher.0.find(code.as_slice()) {
+        let (text_len, typ) = match matcher.0.find(code.as_slice()) {
             Some(m) => (m.start(), matcher.1[m.pattern()]),
             None => (code.rem(), Text),
         };
+        let (text_len, mut typ) = match find_template_in_text(code, text_len) {
+            Some(start) => (start, Template),
+            None => (text_len, typ),
+        };
         // Due to dropped malformed code, it's possible for two or more text nodes to be contiguous. Ensure they always get merged into one.
         // NOTE: Even though bangs/comments/etc. have no effect on layout, they still split text (e.g. `&am<!-- -->p`).
         if text_len > 0 {
+            let start = code.offset();
             let text = decode_entities(code.slice_and_shift(text_len), false);
             match nodes.last_mut() {
-                Some(NodeData::Text { value }) => value.extend_from_slice(&text),
-                _ => nodes.push(NodeData::Text { value: text }),
+                Some(NodeData::Text { value, span }) => {
+                    value.extend_from_slice(&text);
+                    span.end = code.offset();
+                }
+                _ => nodes.push(NodeData::Text {
+                    value: text,
+                    span: code.span_from(start),
+                }),
             };
         };
         // Check using Parsing.md tag rules.
@@ -168,6 +208,10 @@ pub fn parse_content(
                 };
             } else if name.is_empty() {
                 // Malformed code, drop until and including next `>`.
+                code.warn(
+                    WarningKind::MalformedClosingTag,
+                    "dropped `</` not followed by a tag name",
+                );
                 typ = MalformedLeftChevronSlash;
             } else if grandparent == name.as_slice() && can_omit_as_last_node(grandparent, parent) {
                 // The upcoming closing tag implicitly closes the current element e.g. `<tr><td>(current position)</tr>`.
@@ -175,9 +219,29 @@ pub fn parse_content(
                 typ = OmittedClosingTag;
             } else if VOID_TAGS.contains(name.as_slice()) {
                 // Closing tag for void element, drop.
+                code.warn(
+                    WarningKind::MismatchedClosingTag,
+                    format!(
+                        "dropped closing tag for void element `{}`",
+                        String::from_utf8_lossy(&name)
+                    ),
+                );
                 typ = IgnoredTag;
+            } else if ns == Namespace::Html
+                && parent == b"table"
+                && name == b"colgroup"
+                && implied_colgroup_start(&nodes).is_some()
+            {
+                typ = ClosingImpliedColgroupTag;
             } else if parent.is_empty() || parent != name.as_slice() {
                 // Closing tag mismatch, drop.
+                code.warn(
+                    WarningKind::MismatchedClosingTag,
+                    format!(
+                        "dropped closing tag `{}` that doesn't match the open element",
+                        String::from_utf8_lossy(&name)
+                    ),
+                );
                 typ = IgnoredTag;
             };
             typ = maybe_ignore_html_head_body(code, typ, parent, &name);
@@ -189,9 +253,35 @@ pub fn parse_content(
                 closing_tag_omitted = false;
                 break;
             }
+            ClosingImpliedColgroupTag => {
+                let children = nodes.split_off(implied_colgroup_start(&nodes).unwrap());
+                let start = children[0].span().start;
+                let content_span = Span::new(start, code.offset());
+                drop(parse_tag(code));
+                nodes.push(NodeData::Element {
+                    attributes: Default::default(),
+                    children,
+                    closing_tag: ElementClosingTag::Present,
+                    content_span,
+                    name: b"colgroup".to_vec(),
+                    // There's no opening tag.
+                    name_span: Span::new(start, start),
+                    namespace: Namespace::Html,
+                    next_sibling_element_name: Vec::new(),
+                    span: code.span_from(start),
+                });
+            }
             Instruction => nodes.push(parse_instruction(code)),
+            Template => nodes.push(parse_template(code)),
             Bang => nodes.push(parse_bang(code)),
-            Comment => nodes.push(parse_comment(code)),
+            Comment => {
+                let comment = parse_comment(code);
+                let minify_off = is_minify_off(&comment);
+                nodes.push(comment);
+                if minify_off {
+                    nodes.push(parse_verbatim(code));
+                };
+            }
             Doctype => nodes.push(parse_doctype(code)),
             MalformedLeftChevronSlash => code.shift(match memchr::memchr(b'>', code.as_slice()) {
                 Some(m) => m + 1,
@@ -4,6 +4,7 @@ use crate::{ast::NodeData, code_gen::codepoints::WHITESPACE, parse::Code};
 
 pub fn parse_doctype(code: &mut Code) -> NodeData {
     debug_assert!(code.as_slice()[..9].eq_ignore_ascii_case(b"<!doctype"));
+    let start = code.offset();
     code.shift(9);
     code.shift_while_in_lookup(WHITESPACE);
     code.shift_if_next_seq_case_insensitive(b"html");
@@ -15,5 +16,8 @@ pub fn parse_doctype(code: &mut Code) -> NodeData {
     let _ = code.copy_and_shift(len);
     // It might be EOF.
     code.shift(matched);
-    NodeData::Doctype { ended: matched > 0 }
+    NodeData::Doctype {
+        ended: matched > 0,
+        span: code.span_from(start),
+    }
 }
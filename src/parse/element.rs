@@ -3,12 +3,10 @@ use std::{
     str::from_utf8,
 };
 
-use rustc_hash::FxHashMap;
-
 use crate::{
-    ast::{AttrVal, ElementClosingTag, NodeData, ScriptOrStyleLang},
+    ast::{AttrVal, Attributes, ElementClosingTag, NodeData, ScriptOrStyleLang, Span},
     code_gen::codepoints::{
-        ATTR_QUOTE, DOUBLE_QUOTE, NOT_UNQUOTED_ATTR_VAL_CHAR, SINGLE_QUOTE, TAG_NAME_CHAR,
+        ATTR_QUOTE, DOUBLE_QUOTE, Lookup, NOT_UNQUOTED_ATTR_VAL_CHAR, SINGLE_QUOTE, TAG_NAME_CHAR,
         WHITESPACE, WHITESPACE_OR_SLASH, WHITESPACE_OR_SLASH_OR_EQUALS_OR_RIGHT_CHEVRON,
     },
     entity::decode::decode_entities,
@@ -16,14 +14,15 @@ use crate::{
         Code,
         content::{ParsedContent, parse_content},
         script::parse_script_content,
-        style::parse_style_content,
+        style::{is_css_only_svg_style, parse_style_content},
         textarea::parse_textarea_content,
         title::parse_title_content,
     },
     spec::{
-        script::JAVASCRIPT_MIME_TYPES,
+        script::{JAVASCRIPT_MIME_TYPES, is_json_script_type},
         tag::{ns::Namespace, void::VOID_TAGS},
     },
+    template::{contains_template, find_template, template_len},
 };
 
 fn parse_tag_name(code: &mut Code) -> Vec<u8> {
@@ -45,8 +44,9 @@ pub fn peek_tag_name(code: &mut Code) -> Vec<u8> {
 // Derive Eq for testing.
 #[derive(Eq, PartialEq)]
 pub struct ParsedTag {
-    pub attributes: FxHashMap<Vec<u8>, AttrVal>,
+    pub attributes: Attributes,
     pub name: Vec<u8>,
+    pub name_span: Span,
     pub self_closing: bool,
 }
 
@@ -65,11 +65,89 @@ impl Debug for ParsedTag {
     }
 }
 
+// Decodes an attribute value up to the next character in `delim_pred`, keeping template tags in it as
+// they are.
+fn parse_attr_value(code: &mut Code, delim_pred: &'static Lookup) -> Vec<u8> {
+    let mut value = Vec::new();
+    loop {
+        let raw = code.as_slice();
+        let len = raw.iter().position(|&c| delim_pred[c]).unwrap_or(raw.len());
+        let Some(start) = find_template(code.template_delimiters, &raw[..len]) else {
+            value.extend_from_slice(&decode_entities(code.slice_and_shift(len), true));
+            return value;
+        };
+        value.extend_from_slice(&decode_entities(code.slice_and_shift(start), true));
+        // Quotes and whitespace inside a template tag don't end the value.
+        let len = template_len(code.template_delimiters, code.as_slice()).unwrap();
+        value.extend_from_slice(code.slice_and_shift(len));
+    }
+}
+
+// Parses an attribute name, keeping template tags in it as they are, so that whitespace or `=` inside
+// one doesn't end it. Template tags between attributes, such as in
+// `<input {% if x %} checked {% endif %}>`, become attributes with no value.
+fn parse_attr_name(code: &mut Code) -> Vec<u8> {
+    let mut name = Vec::new();
+    // An attribute name can start with `=`, but ends at the next whitespace, `=`, `/`, or `>`.
+    if template_len(code.template_delimiters, code.as_slice()).is_none()
+        && let Some(c) = code.shift_if_next_not_in_lookup(WHITESPACE_OR_SLASH)
+    {
+        name.push(c.to_ascii_lowercase());
+    };
+    loop {
+        let raw = code.as_slice();
+        let len = raw
+            .iter()
+            .position(|&c| WHITESPACE_OR_SLASH_OR_EQUALS_OR_RIGHT_CHEVRON[c])
+            .unwrap_or(raw.len());
+        let Some(start) = find_template(code.template_delimiters, &raw[..len]) else {
+            name.extend(code.slice_and_shift(len).iter().map(u8::to_ascii_lowercase));
+            return name;
+        };
+        name.extend(
+            code.slice_and_shift(start)
+                .iter()
+                .map(u8::to_ascii_lowercase),
+        );
+        let len = template_len(code.template_delimiters, code.as_slice()).unwrap();
+        name.extend_from_slice(code.slice_and_shift(len));
+    }
+}
+
+// Attributes repeated between template tags, such as in
+// `<a {% if x %} class=b {% else %} class=c {% endif %}>`, can't all be kept as attributes, so
+// everything from the first template tag up to the end of the tag is kept as the name of a single
+// attribute with no value instead.
+fn parse_templated_attrs(code: &mut Code) -> Vec<u8> {
+    let src = code.as_slice();
+    let mut len = 0;
+    while let Some(&c) = src.get(len) {
+        if let Some(n) = template_len(code.template_delimiters, &src[len..]) {
+            len += n;
+        } else if c == b'>' {
+            break;
+        } else if c == b'"' || c == b'\'' {
+            len += 1 + memchr::memchr(c, &src[len + 1..]).map_or(src.len() - len - 1, |n| n + 1);
+        } else {
+            len += 1;
+        };
+    }
+    // Leave any `/` for the self-closing check.
+    while len > 0 && WHITESPACE_OR_SLASH[src[len - 1]] {
+        len -= 1;
+    }
+    code.copy_and_shift(len)
+}
+
 // While not valid, attributes in closing tags still need to be parsed (and then discarded) as attributes e.g. `</div x=">">`, which is why this function is used for both opening and closing tags.
 // TODO Use generics to create version that doesn't create an AHashMap.
 pub fn parse_tag(code: &mut Code) -> ParsedTag {
     let elem_name = parse_tag_name(code);
-    let mut attributes = FxHashMap::default();
+    let name_span = Span::new(code.offset() - elem_name.len(), code.offset());
+    let mut attributes = Attributes::default();
+    // Where the first attribute with a template tag in its name starts, and how many attributes came
+    // before it.
+    let mut first_templated = None;
     let self_closing;
     loop {
         // At the beginning of this loop, the last parsed unit was either the tag name or an attribute (including its value, if it had one).
@@ -79,30 +157,29 @@ pub fn parse_tag(code: &mut Code) -> ParsedTag {
             // End of tag.
             break;
         };
-        let mut attr_name = Vec::new();
-        // An attribute name can start with `=`, but ends at the next whitespace, `=`, `/`, or `>`.
-        if let Some(c) = code.shift_if_next_not_in_lookup(WHITESPACE_OR_SLASH) {
-            attr_name.push(c);
-        };
-        attr_name.extend_from_slice(
-            code.slice_and_shift_while_not_in_lookup(
-                WHITESPACE_OR_SLASH_OR_EQUALS_OR_RIGHT_CHEVRON,
-            ),
-        );
+        let name_start = code.offset();
+        let name_checkpoint = code.take_checkpoint();
+        let attr_name = parse_attr_name(code);
         debug_assert!(!attr_name.is_empty());
-        attr_name.make_ascii_lowercase();
+        if first_templated.is_none() && contains_template(code.template_delimiters, &attr_name) {
+            first_templated = Some((name_start, name_checkpoint, attributes.len()));
+        };
+        let name_span = code.span_from(name_start);
         // See comment for WHITESPACE_OR_SLASH in codepoints.ts for details of complex attr parsing.
         code.shift_while_in_lookup(WHITESPACE);
         let has_value = code.shift_if_next(b'=');
         code.shift_while_in_lookup(WHITESPACE);
         let attr_value = if !has_value {
             AttrVal {
+                name_span,
+                span: code.span_from(code.offset()),
                 quote: None,
                 value: Vec::new(),
             }
         } else {
             // TODO Replace ATTR_QUOTE with direct comparison.
             let attr_delim = code.shift_if_next_in_lookup(ATTR_QUOTE);
+            let start = code.offset();
             // It seems that for unquoted attribute values, if it's the last value in a tag and is immediately followed by `>`, any trailing `/` is NOT interpreted as a self-closing indicator and is always included as part of the value, even for SVG self-closable elements.
             let attr_delim_pred = match attr_delim {
                 Some(b'"') => DOUBLE_QUOTE,
@@ -110,41 +187,83 @@ pub fn parse_tag(code: &mut Code) -> ParsedTag {
                 None => NOT_UNQUOTED_ATTR_VAL_CHAR,
                 _ => unreachable!(),
             };
-            let attr_value = decode_entities(
-                code.slice_and_shift_while_not_in_lookup(attr_delim_pred),
-                true,
-            );
+            let attr_value = parse_attr_value(code, attr_delim_pred);
+            let span = code.span_from(start);
             if let Some(c) = attr_delim {
                 // It might not be next if EOF (i.e. attribute value not closed).
                 code.shift_if_next(c);
             };
             AttrVal {
+                name_span,
+                span,
                 quote: attr_delim,
                 value: attr_value,
             }
         };
+        if let Some((start, cp, len)) =
+            first_templated.filter(|_| attributes.contains_key(&attr_name))
+        {
+            code.restore_checkpoint(cp);
+            attributes.truncate(len);
+            let name = parse_templated_attrs(code);
+            attributes.insert(
+                name,
+                AttrVal {
+                    name_span: code.span_from(start),
+                    span: code.span_from(code.offset()),
+                    quote: None,
+                    value: Vec::new(),
+                },
+            );
+            continue;
+        };
         attributes.insert(attr_name, attr_value);
     }
     ParsedTag {
         attributes,
         name: elem_name,
+        name_span,
         self_closing,
     }
 }
 
+// Elements in MathML token elements are parsed as HTML, except for <mglyph> and <malignmark>:
+// https://html.spec.whatwg.org/multipage/parsing.html#mathml-text-integration-point.
+fn is_mathml_text_integration_point(parent: &[u8], name: &[u8]) -> bool {
+    matches!(parent, b"mi" | b"mn" | b"mo" | b"ms" | b"mtext")
+        && !matches!(name, b"mglyph" | b"malignmark")
+}
+
+// `annotation-xml` elements with an HTML `encoding` are HTML integration points, whose content is
+// parsed as HTML: https://html.spec.whatwg.org/multipage/parsing.html#html-integration-point.
+fn is_html_integration_point(ns: Namespace, name: &[u8], attributes: &Attributes) -> bool {
+    ns == Namespace::MathMl
+        && name == b"annotation-xml"
+        && attributes.get(b"encoding".as_slice()).is_some_and(|e| {
+            e.value.eq_ignore_ascii_case(b"text/html")
+                || e.value.eq_ignore_ascii_case(b"application/xhtml+xml")
+        })
+}
+
 // `<` must be next. `parent` should be an empty slice if it doesn't exist.
 pub fn parse_element(code: &mut Code, ns: Namespace, parent: &[u8]) -> NodeData {
+    let start = code.offset();
     let ParsedTag {
         name: elem_name,
+        name_span,
         attributes,
         self_closing,
     } = parse_tag(code);
+    let content_start = code.offset();
 
-    // Embedded svg tags are immediately in the svg namespace and must be parsed as such.
-    let ns = if elem_name == b"svg" {
-        Namespace::Svg
-    } else {
-        ns
+    // Embedded svg and math tags are immediately in their namespace and must be parsed as such.
+    let ns = match elem_name.as_slice() {
+        b"svg" => Namespace::Svg,
+        b"math" => Namespace::MathMl,
+        name if ns == Namespace::MathMl && is_mathml_text_integration_point(parent, name) => {
+            Namespace::Html
+        }
+        _ => ns,
     };
 
     // Only foreign elements can be self closed.
@@ -153,9 +272,12 @@ pub fn parse_element(code: &mut Code, ns: Namespace, parent: &[u8]) -> NodeData
             attributes,
             children: Vec::new(),
             closing_tag: ElementClosingTag::SelfClosing,
+            content_span: code.span_from(content_start),
             name: elem_name,
+            name_span,
             namespace: ns,
             next_sibling_element_name: Vec::new(),
+            span: code.span_from(start),
         };
     };
     if VOID_TAGS.contains(elem_name.as_slice()) {
@@ -163,9 +285,12 @@ pub fn parse_element(code: &mut Code, ns: Namespace, parent: &[u8]) -> NodeData
             attributes,
             children: Vec::new(),
             closing_tag: ElementClosingTag::Void,
+            content_span: code.span_from(content_start),
             name: elem_name,
+            name_span,
             namespace: ns,
             next_sibling_element_name: Vec::new(),
+            span: code.span_from(start),
         };
     };
 
@@ -177,17 +302,27 @@ pub fn parse_element(code: &mut Code, ns: Namespace, parent: &[u8]) -> NodeData
             Some(typ) if typ.as_slice() == b"module" => {
                 parse_script_content(code, ScriptOrStyleLang::JSModule)
             }
+            Some(typ) if is_json_script_type(typ.as_slice()) => {
+                parse_script_content(code, ScriptOrStyleLang::JSON)
+            }
             Some(mime) if !JAVASCRIPT_MIME_TYPES.contains(mime.as_slice()) => {
                 parse_script_content(code, ScriptOrStyleLang::Data)
             }
             _ => parse_script_content(code, ScriptOrStyleLang::JS),
         },
+        (Namespace::Svg, b"style") if !is_css_only_svg_style(code) => {
+            parse_content(code, ns, parent, &elem_name)
+        }
         (_, b"style") => parse_style_content(code),
         (Namespace::Html, b"textarea") => parse_textarea_content(code),
         (Namespace::Html, b"title") => parse_title_content(code),
+        _ if is_html_integration_point(ns, &elem_name, &attributes) => {
+            parse_content(code, Namespace::Html, parent, &elem_name)
+        }
         _ => parse_content(code, ns, parent, &elem_name),
     };
 
+    let content_span = code.span_from(content_start);
     if !closing_tag_omitted {
         let closing_tag = parse_tag(code);
         debug_assert_eq!(closing_tag.name, elem_name);
@@ -201,8 +336,11 @@ pub fn parse_element(code: &mut Code, ns: Namespace, parent: &[u8]) -> NodeData
         } else {
             ElementClosingTag::Present
         },
+        content_span,
         name: elem_name,
+        name_span,
         namespace: ns,
         next_sibling_element_name: Vec::new(),
+        span: code.span_from(start),
     }
 }
@@ -2,7 +2,7 @@ use std::sync::LazyLock;
 
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, AhoCorasickKind};
 
-use crate::{ast::NodeData, parse::Code};
+use crate::{ast::NodeData, parse::Code, report::WarningKind};
 
 static INSTRUCTION_END: LazyLock<AhoCorasick> = LazyLock::new(|| {
     AhoCorasickBuilder::new()
@@ -13,6 +13,7 @@ static INSTRUCTION_END: LazyLock<AhoCorasick> = LazyLock::new(|| {
 
 pub fn parse_instruction(code: &mut Code) -> NodeData {
     debug_assert!(code.as_slice().starts_with(b"<?"));
+    let start = code.offset();
     code.shift(2);
     let (len, matched) = match INSTRUCTION_END.find(code.as_slice()) {
         Some(m) => (m.start(), m.end() - m.start()),
@@ -21,8 +22,16 @@ pub fn parse_instruction(code: &mut Code) -> NodeData {
     let data = code.copy_and_shift(len);
     // It might be EOF.
     code.shift(matched);
+    if matched == 0 {
+        code.warn_at(
+            start,
+            WarningKind::UnclosedInstruction,
+            "processing instruction is not closed",
+        );
+    };
     NodeData::Instruction {
         code: data,
         ended: matched > 0,
+        span: code.span_from(start),
     }
 }
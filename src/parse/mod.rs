@@ -1,4 +1,9 @@
-use crate::code_gen::codepoints::Lookup;
+use crate::{
+    ast::Span,
+    code_gen::codepoints::Lookup,
+    report::{Warning, WarningKind},
+    template::TemplateDelimiters,
+};
 
 pub mod bang;
 pub mod comment;
@@ -12,6 +17,7 @@ pub mod style;
 mod tests;
 pub mod textarea;
 pub mod title;
+pub mod verbatim;
 
 pub struct Code<'c> {
     code: &'c [u8],
@@ -21,13 +27,21 @@ pub struct Code<'c> {
     pub seen_head_open: bool,
     pub seen_head_close: bool,
     pub seen_body_open: bool,
+
+    // Template tags that are kept as is. See `Cfg::template_delimiters`.
+    pub template_delimiters: &'c TemplateDelimiters,
+
+    pub warnings: Vec<Warning>,
 }
 
 #[derive(Copy, Clone)]
 pub struct Checkpoint(usize);
 
-impl Code<'_> {
-    pub const fn new_with_opts(code: &[u8]) -> Code {
+impl<'c> Code<'c> {
+    pub const fn new_with_opts(
+        code: &'c [u8],
+        template_delimiters: &'c TemplateDelimiters,
+    ) -> Code<'c> {
         Code {
             code,
             next: 0,
@@ -35,14 +49,39 @@ impl Code<'_> {
             seen_head_open: false,
             seen_head_close: false,
             seen_body_open: false,
+            template_delimiters,
+            warnings: Vec::new(),
         }
     }
 
-    pub fn new(code: &[u8]) -> Code {
-        Code::new_with_opts(code)
+    #[cfg(test)]
+    pub fn new(code: &'c [u8]) -> Code<'c> {
+        Code::new_with_opts(code, &[])
+    }
+
+    // Offset of the next character in the source.
+    pub const fn offset(&self) -> usize {
+        self.next
+    }
+
+    // Span from `start` up to the next character.
+    pub const fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.next)
+    }
+
+    pub fn warn(&mut self, kind: WarningKind, message: impl Into<String>) {
+        self.warn_at(self.next, kind, message);
+    }
+
+    pub fn warn_at(&mut self, offset: usize, kind: WarningKind, message: impl Into<String>) {
+        self.warnings.push(Warning {
+            kind,
+            offset,
+            message: message.into(),
+        });
     }
 
-    pub fn as_slice(&self) -> &[u8] {
+    pub fn as_slice(&self) -> &'c [u8] {
         &self.code[self.next..]
     }
 
@@ -123,17 +162,6 @@ impl Code<'_> {
         self.copy_and_shift(len)
     }
 
-    pub fn slice_and_shift_while_not_in_lookup(&mut self, lookup: &'static Lookup) -> &[u8] {
-        let mut len = 0;
-        loop {
-            match self.code.get(self.next + len) {
-                Some(&c) if !lookup[c] => len += 1,
-                _ => break,
-            };
-        }
-        self.slice_and_shift(len)
-    }
-
     // Returns the last character matched.
     pub fn shift_while_in_lookup(&mut self, lookup: &'static Lookup) -> Option<u8> {
         let mut last: Option<u8> = None;
@@ -3,7 +3,7 @@ use std::sync::LazyLock;
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, AhoCorasickKind};
 
 use crate::{
-    ast::{NodeData, ScriptOrStyleLang},
+    ast::{NodeData, ScriptOrStyleLang, Span},
     parse::{Code, content::ParsedContent},
 };
 
@@ -20,11 +20,13 @@ pub fn parse_script_content(code: &mut Code, lang: ScriptOrStyleLang) -> ParsedC
         Some(m) => (m.start(), false),
         None => (code.rem(), true),
     };
+    let start = code.offset();
     ParsedContent {
         closing_tag_omitted,
         children: vec![NodeData::ScriptOrStyleContent {
             code: code.copy_and_shift(len),
             lang,
+            span: Span::new(start, start + len),
         }],
     }
 }
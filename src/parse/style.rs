@@ -1,12 +1,17 @@
 use std::sync::LazyLock;
 
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, AhoCorasickKind};
+use memchr::{memchr, memmem};
 
 use crate::{
-    ast::{NodeData, ScriptOrStyleLang},
+    ast::{NodeData, ScriptOrStyleLang, Span},
+    entity::decode::decode_entities,
     parse::{Code, content::ParsedContent},
 };
 
+const CDATA_START: &[u8] = b"<![CDATA[";
+const CDATA_END: &[u8] = b"]]>";
+
 static END: LazyLock<AhoCorasick> = LazyLock::new(|| {
     AhoCorasickBuilder::new()
         .ascii_case_insensitive(true)
@@ -20,11 +25,46 @@ pub fn parse_style_content(code: &mut Code) -> ParsedContent {
         Some(m) => (m.start(), false),
         None => (code.rem(), true),
     };
+    let start = code.offset();
     ParsedContent {
         closing_tag_omitted,
         children: vec![NodeData::ScriptOrStyleContent {
             code: code.copy_and_shift(len),
             lang: ScriptOrStyleLang::CSS,
+            span: Span::new(start, start + len),
         }],
     }
 }
+
+// `<style>` in SVG is parsed like other foreign content, so it can contain elements and comments. It's
+// only parsed as CSS code if it has no `<` other than those starting CDATA sections.
+pub fn is_css_only_svg_style(code: &Code) -> bool {
+    let src = code.as_slice();
+    let mut rest = &src[..END.find(src).map_or(src.len(), |m| m.start())];
+    while let Some(i) = memchr(b'<', rest) {
+        rest = &rest[i..];
+        if !rest.starts_with(CDATA_START) {
+            return false;
+        };
+        match memmem::find(rest, CDATA_END) {
+            Some(end) => rest = &rest[end + CDATA_END.len()..],
+            None => return true,
+        };
+    }
+    true
+}
+
+// Gets the CSS code of a `<style>` in SVG by decoding entities and unwrapping CDATA sections.
+pub fn decode_svg_style(code: &[u8]) -> Vec<u8> {
+    let mut css = Vec::with_capacity(code.len());
+    let mut rest = code;
+    while let Some(start) = memmem::find(rest, CDATA_START) {
+        css.extend_from_slice(&decode_entities(&rest[..start], false));
+        rest = &rest[start + CDATA_START.len()..];
+        let end = memmem::find(rest, CDATA_END).unwrap_or(rest.len());
+        css.extend_from_slice(&rest[..end]);
+        rest = &rest[(end + CDATA_END.len()).min(rest.len())..];
+    }
+    css.extend_from_slice(&decode_entities(rest, false));
+    css
+}
@@ -1,7 +1,5 @@
-use rustc_hash::FxHashMap;
-
 use crate::{
-    ast::{AttrVal, ElementClosingTag, NodeData},
+    ast::{AttrVal, Attributes, ElementClosingTag, NodeData, Span},
     parse::{
         Code,
         element::{ParsedTag, parse_element, parse_tag},
@@ -10,10 +8,7 @@ use crate::{
 };
 
 fn val(v: &[u8]) -> AttrVal {
-    AttrVal {
-        value: v.to_vec(),
-        quote: None,
-    }
+    AttrVal::new(v)
 }
 
 #[test]
@@ -30,7 +25,7 @@ fn test_parse_tag() {
         tag,
         ParsedTag {
             attributes: {
-                let mut map = FxHashMap::<Vec<u8>, AttrVal>::default();
+                let mut map = Attributes::default();
                 map.insert(b"type".to_vec(), val(b"password"));
                 map.insert(b"\"a\"".to_vec(), val(b"  b  "));
                 map.insert(b":cd".to_vec(), val(b""));
@@ -47,6 +42,7 @@ fn test_parse_tag() {
                 map
             },
             name: b"input".to_vec(),
+            name_span: Span::new(1, 6),
             self_closing: false,
         }
     );
@@ -60,15 +56,53 @@ fn test_parse_element() {
         elem,
         NodeData::Element {
             attributes: {
-                let mut map = FxHashMap::<Vec<u8>, AttrVal>::default();
+                let mut map = Attributes::default();
                 map.insert(b"b".to_vec(), val(br#"\"c\""#));
                 map
             },
             children: vec![],
             closing_tag: ElementClosingTag::Present,
+            content_span: Span::new(10, 10),
             name: b"a".to_vec(),
+            name_span: Span::new(1, 2),
             namespace: Namespace::Html,
             next_sibling_element_name: Vec::new(),
+            span: Span::new(0, 15),
         }
     );
+    assert_eq!(elem.span(), Span::new(0, 15));
+}
+
+#[test]
+fn test_parse_spans() {
+    let src = b"<div id=a title = 'b' hidden>x</DIV >";
+    let mut code = Code::new(src);
+    let NodeData::Element {
+        attributes,
+        children,
+        content_span,
+        name_span,
+        span,
+        ..
+    } = parse_element(&mut code, Namespace::Html, EMPTY_SLICE)
+    else {
+        panic!("not an element");
+    };
+    let slice = |s: Span| &src[s.start..s.end];
+    assert_eq!(span, Span::new(0, src.len()));
+    assert_eq!(slice(name_span), b"div");
+    assert_eq!(slice(content_span), b"x");
+    assert_eq!(children[0].span(), content_span);
+    let attrs = attributes
+        .iter()
+        .map(|(_, v)| (slice(v.name_span), slice(v.span)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        attrs,
+        [
+            (b"id".as_slice(), b"a".as_slice()),
+            (b"title", b"b"),
+            (b"hidden", b""),
+        ]
+    );
 }
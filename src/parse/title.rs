@@ -1,8 +1,8 @@
 use crate::{
     ast::{NodeData, RcdataContentType},
-    entity::decode::decode_entities,
     parse::{Code, content::ParsedContent},
     tag::TAG_TITLE_END,
+    template::decode_templated,
 };
 
 pub fn parse_title_content(code: &mut Code) -> ParsedContent {
@@ -10,11 +10,16 @@ pub fn parse_title_content(code: &mut Code) -> ParsedContent {
         Some(m) => (m.start(), false),
         None => (code.rem(), true),
     };
+    let start = code.offset();
+    // Template tags are kept as they are, like in attribute values.
+    let delims = code.template_delimiters;
+    let text = decode_templated(delims, code.slice_and_shift(len));
     ParsedContent {
         closing_tag_omitted,
         children: vec![NodeData::RcdataContent {
             typ: RcdataContentType::Title,
-            text: decode_entities(code.slice_and_shift(len), false),
+            text,
+            span: code.span_from(start),
         }],
     }
 }
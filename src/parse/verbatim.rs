@@ -0,0 +1,54 @@
+use memchr::memmem;
+
+use crate::{
+    ast::NodeData,
+    parse::Code,
+    template::{find_template, template_len},
+    whitespace::trimmed,
+};
+
+fn is_marker(comment: &[u8], marker: &[u8]) -> bool {
+    trimmed(comment).eq_ignore_ascii_case(marker)
+}
+
+// Whether a comment starts a region that should be passed through as is.
+pub fn is_minify_off(node: &NodeData) -> bool {
+    matches!(node, NodeData::Comment { code, ended: true, .. } if is_marker(code, b"minify:off"))
+}
+
+// Takes everything up to the next `<!-- minify:on -->` comment or the end of the source, leaving the
+// comment to be parsed normally.
+pub fn parse_verbatim(code: &mut Code) -> NodeData {
+    let start = code.offset();
+    let src = code.as_slice();
+    let len = memmem::find_iter(src, b"<!--")
+        .find(|&start| {
+            let rest = &src[start + 4..];
+            memmem::find(rest, b"-->").is_some_and(|end| is_marker(&rest[..end], b"minify:on"))
+        })
+        .unwrap_or(src.len());
+    NodeData::Verbatim {
+        code: code.copy_and_shift(len),
+        span: code.span_from(start),
+    }
+}
+
+// Position of the next template tag if it starts within the first `len` bytes, which would otherwise
+// be parsed as text.
+pub fn find_template_in_text(code: &Code, len: usize) -> Option<usize> {
+    let delims = code.template_delimiters;
+    let max_open = delims.iter().map(|(open, _)| open.len()).max()?;
+    // Only search a little past `len`, as a template tag can contain a match for a tag or comment.
+    let src = code.as_slice();
+    find_template(delims, &src[..(len + max_open).min(src.len())]).filter(|&start| start <= len)
+}
+
+// A template tag must be next.
+pub fn parse_template(code: &mut Code) -> NodeData {
+    let start = code.offset();
+    let len = template_len(code.template_delimiters, code.as_slice()).unwrap();
+    NodeData::Verbatim {
+        code: code.copy_and_shift(len),
+        span: code.span_from(start),
+    }
+}
@@ -0,0 +1,56 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+};
+
+/// The kind of problem a [`Warning`] describes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WarningKind {
+    /// A second `<html>`, `<head>`, or `<body>` opening tag, or a second `</head>`, which was ignored.
+    IgnoredDuplicateTag,
+    /// `<style>` content or a `style` attribute that could not be parsed or minified, and was kept as is.
+    InvalidCss,
+    /// `<script>` content that could not be parsed, and was kept as is.
+    InvalidJs,
+    /// JSON `<script>` content that could not be parsed, and was kept as is.
+    InvalidJson,
+    /// `<style>` content, `<script>` content, or a `style` attribute that is not valid UTF-8. It is
+    /// kept as is unless [`Cfg::decode_invalid_utf8_lossily`](crate::Cfg::decode_invalid_utf8_lossily)
+    /// is set.
+    InvalidUtf8,
+    /// A `</` not followed by a tag name; everything up to and including the next `>` was dropped.
+    MalformedClosingTag,
+    /// A closing tag that doesn't match the open element, and was dropped.
+    MismatchedClosingTag,
+    /// A bang (e.g. `<!x`) that reached the end of the source before `>`.
+    UnclosedBang,
+    /// A comment that reached the end of the source before `-->`.
+    UnclosedComment,
+    /// A processing instruction that reached the end of the source before `?>`.
+    UnclosedInstruction,
+}
+
+/// A problem found in the source that didn't stop minification.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    /// Byte offset into the source code where the problem was found.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.offset, self.message)
+    }
+}
+
+impl Error for Warning {}
+
+/// The result of [`minify_with_report`](crate::minify_with_report).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Report {
+    pub output: Vec<u8>,
+    /// Sorted by offset.
+    pub warnings: Vec<Warning>,
+}
@@ -0,0 +1,222 @@
+use std::fmt::Write;
+
+use memchr::memchr_iter;
+
+#[cfg(any(feature = "css", feature = "js"))]
+use crate::minify::output::Output;
+
+/// A point in the minified output that maps back to the source, as byte offsets into each.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Mapping {
+    pub generated: usize,
+    pub original: usize,
+}
+
+/// A map from minified output back to the source it came from, as returned by
+/// [`minify_with_source_map`](crate::minify_with_source_map).
+///
+/// Every node, attribute name, and closing tag maps to where it starts in the source, and the code of
+/// `<script>` and `<style>` elements maps to the tokens it was minified from when the `js` and `css`
+/// features are enabled.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceMap {
+    source_name: String,
+    source: String,
+    mappings: Vec<Mapping>,
+    // The `mappings` field of the JSON, which needs the output to be computed.
+    encoded: String,
+}
+
+impl SourceMap {
+    pub(crate) fn new(
+        source_name: &str,
+        source: &[u8],
+        output: &[u8],
+        mappings: Vec<Mapping>,
+    ) -> SourceMap {
+        SourceMap {
+            source_name: source_name.to_string(),
+            source: String::from_utf8_lossy(source).into_owned(),
+            encoded: encode_mappings(source, output, &mappings),
+            mappings,
+        }
+    }
+
+    /// The name of the source in the map, which devtools show and resolve relative to the map.
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+
+    /// The mappings, ordered by their offset in the output.
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    /// Returns the offset in the source of what produced the output at `generated`, which is that of
+    /// the closest mapping at or before it.
+    pub fn original_offset(&self, generated: usize) -> Option<usize> {
+        let i = self.mappings.partition_point(|m| m.generated <= generated);
+        i.checked_sub(1).map(|i| self.mappings[i].original)
+    }
+
+    /// Serialises the map as [version 3](https://tc39.es/ecma426/) JSON, with the source embedded in
+    /// `sourcesContent`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from(r#"{"version":3,"sources":["#);
+        push_json_string(&mut json, &self.source_name);
+        json.push_str(r#"],"sourcesContent":["#);
+        push_json_string(&mut json, &self.source);
+        json.push_str(r#"],"names":[],"mappings":"#);
+        push_json_string(&mut json, &self.encoded);
+        json.push('}');
+        json
+    }
+}
+
+// Number of UTF-16 code units a UTF-8 byte adds to a column, so that a character is counted at its
+// first byte only.
+fn utf16_len(b: u8) -> usize {
+    match b {
+        0x80..=0xBF => 0,
+        0xF0..=0xFF => 2,
+        _ => 1,
+    }
+}
+
+// Converts between byte offsets and the zero-based lines and columns of source maps.
+struct LineIndex<'t> {
+    text: &'t [u8],
+    line_starts: Vec<usize>,
+}
+
+impl<'t> LineIndex<'t> {
+    fn new(text: &'t [u8]) -> LineIndex<'t> {
+        let mut line_starts = vec![0];
+        line_starts.extend(memchr_iter(b'\n', text).map(|i| i + 1));
+        LineIndex { text, line_starts }
+    }
+
+    // Line and UTF-16 column of a byte offset.
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&s| s <= offset) - 1;
+        let start = self.line_starts[line];
+        let column = self.text[start..offset].iter().map(|&b| utf16_len(b)).sum();
+        (line, column)
+    }
+
+    // Byte offset of a column, which counts UTF-16 code units if `utf16` or bytes otherwise. Columns
+    // past the end of the line are clamped to it.
+    #[cfg(any(feature = "css", feature = "js"))]
+    fn offset(&self, line: u32, column: u32, utf16: bool) -> Option<usize> {
+        let start = *self.line_starts.get(line as usize)?;
+        let end = self
+            .line_starts
+            .get(line as usize + 1)
+            .map_or(self.text.len(), |&s| s - 1);
+        let column = column as usize;
+        if !utf16 {
+            return Some(start + column.min(end - start));
+        };
+        let mut units = 0;
+        for (i, &b) in self.text[start..end].iter().enumerate() {
+            let len = utf16_len(b);
+            if len > 0 && units >= column {
+                return Some(start + i);
+            };
+            units += len;
+        }
+        Some(end)
+    }
+}
+
+// Adds the mappings of code minified by another tool, such as oxc for `<script>` or lightningcss for
+// `<style>`, that's about to be written to `out` without its first `skip` bytes. Each token has the
+// line and column in `minified`, then the line and column in `source`, which starts at `original` in
+// the HTML source. Source columns count UTF-16 code units, and so do minified columns if
+// `utf16_generated`, otherwise they count bytes.
+#[cfg(any(feature = "css", feature = "js"))]
+pub fn add_inner_mappings(
+    out: &mut Output,
+    minified: &str,
+    skip: usize,
+    source: &str,
+    original: usize,
+    utf16_generated: bool,
+    tokens: impl IntoIterator<Item = [u32; 4]>,
+) {
+    let generated = out.position();
+    let minified = LineIndex::new(minified.as_bytes());
+    let source = LineIndex::new(source.as_bytes());
+    for [dst_line, dst_col, src_line, src_col] in tokens {
+        if let Some(dst) = minified
+            .offset(dst_line, dst_col, utf16_generated)
+            .filter(|&dst| dst >= skip)
+            && let Some(src) = source.offset(src_line, src_col, true)
+        {
+            out.add_mapping_at(generated + dst - skip, original + src);
+        };
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Appends a base64 VLQ, the encoding of numbers in source map mappings.
+fn push_vlq(out: &mut String, value: isize) {
+    let mut v = (value.unsigned_abs() << 1) | usize::from(value < 0);
+    loop {
+        let digit = v & 31;
+        v >>= 5;
+        out.push(BASE64[digit | if v > 0 { 32 } else { 0 }] as char);
+        if v == 0 {
+            break;
+        };
+    }
+}
+
+// Encodes mappings as lines of segments separated by `;`, each segment being the generated column,
+// source index, original line, and original column, relative to those of the previous segment.
+fn encode_mappings(source: &[u8], output: &[u8], mappings: &[Mapping]) -> String {
+    let source = LineIndex::new(source);
+    let output = LineIndex::new(output);
+    let mut encoded = String::new();
+    let mut line = 0;
+    let mut prev_column = 0;
+    let mut prev_original = (0, 0);
+    for m in mappings {
+        let (gen_line, gen_column) = output.position(m.generated);
+        if gen_line > line {
+            encoded.extend(std::iter::repeat_n(';', gen_line - line));
+            line = gen_line;
+            prev_column = 0;
+        } else if !encoded.is_empty() && !encoded.ends_with(';') {
+            encoded.push(',');
+        };
+        let (orig_line, orig_column) = source.position(m.original);
+        push_vlq(&mut encoded, gen_column as isize - prev_column as isize);
+        push_vlq(&mut encoded, 0);
+        push_vlq(&mut encoded, orig_line as isize - prev_original.0 as isize);
+        push_vlq(
+            &mut encoded,
+            orig_column as isize - prev_original.1 as isize,
+        );
+        prev_column = gen_column;
+        prev_original = (orig_line, orig_column);
+    }
+    encoded
+}
+
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        };
+    }
+    out.push('"');
+}
@@ -22,3 +22,12 @@ pub static JAVASCRIPT_MIME_TYPES: LazyLock<FxHashSet<&'static [u8]>> = LazyLock:
     s.insert(b"text/x-javascript");
     s
 });
+
+// Script types whose content is JSON, and so can be minified as such.
+pub fn is_json_script_type(typ: &[u8]) -> bool {
+    let typ = typ.to_ascii_lowercase();
+    matches!(
+        typ.as_slice(),
+        b"application/json" | b"importmap" | b"speculationrules"
+    ) || typ.ends_with(b"+json")
+}
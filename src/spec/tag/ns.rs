@@ -1,5 +1,7 @@
+/// Namespace an element was parsed in.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Namespace {
     Html,
+    MathMl,
     Svg,
 }
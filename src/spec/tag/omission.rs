@@ -1,5 +1,4 @@
 // Rules sourced from https://html.spec.whatwg.org/multipage/syntax.html#syntax-tag-omission.
-// TODO Opening tags
 
 use std::sync::LazyLock;
 
@@ -275,3 +274,53 @@ pub fn can_omit_as_before(before: &[u8], after: &[u8]) -> bool {
         .filter(|r| r.followed_by.contains(after))
         .is_some()
 }
+
+// The first node that will be written inside an element.
+pub enum FirstChild<'a> {
+    // The element will be empty.
+    None,
+    // A comment, bang, or processing instruction, which are all parsed as comments.
+    Comment,
+    Element(&'a [u8]),
+    Text,
+    // Text that starts with whitespace.
+    Whitespace,
+}
+
+// Whether the opening tag of an element without attributes can be omitted. Use an empty slice for
+// `previous` if the previous text or element sibling isn't an element, and only provide it if its
+// closing tag has been omitted.
+pub fn can_omit_opening_tag(tag: &[u8], first_child: FirstChild, previous: &[u8]) -> bool {
+    match tag {
+        b"html" => !matches!(first_child, FirstChild::Comment),
+        b"head" => matches!(first_child, FirstChild::None | FirstChild::Element(_)),
+        b"body" => match first_child {
+            FirstChild::None | FirstChild::Text => true,
+            FirstChild::Comment | FirstChild::Whitespace => false,
+            // These would be put in the `<head>` instead.
+            FirstChild::Element(name) => !matches!(
+                name,
+                b"base"
+                    | b"basefont"
+                    | b"bgsound"
+                    | b"link"
+                    | b"meta"
+                    | b"noframes"
+                    | b"noscript"
+                    | b"script"
+                    | b"style"
+                    | b"template"
+                    | b"title"
+            ),
+        },
+        // Otherwise the `<col>` or `<tr>` would be placed in the previous element.
+        b"colgroup" => {
+            matches!(first_child, FirstChild::Element(b"col")) && previous != b"colgroup"
+        }
+        b"tbody" => {
+            matches!(first_child, FirstChild::Element(b"tr"))
+                && !matches!(previous, b"tbody" | b"thead" | b"tfoot")
+        }
+        _ => false,
+    }
+}
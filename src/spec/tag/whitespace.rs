@@ -60,6 +60,14 @@ static DEFAULT_SVG: &WhitespaceMinification = &WhitespaceMinification {
     trim: true,
 };
 
+// MathML Core lays out unknown elements like <mrow>, which ignores whitespace between its children:
+// https://w3c.github.io/mathml-core/#unknown-elements.
+static DEFAULT_MATHML: &WhitespaceMinification = &WhitespaceMinification {
+    collapse: true,
+    destroy_whole: true,
+    trim: true,
+};
+
 static HTML_TAG_WHITESPACE_MINIFICATION: LazyLock<
     FxHashMap<&'static [u8], &'static WhitespaceMinification>,
 > = LazyLock::new(|| {
@@ -276,6 +284,25 @@ static SVG_TAG_WHITESPACE_MINIFICATION: LazyLock<
     m
 });
 
+static MATHML_TAG_WHITESPACE_MINIFICATION: LazyLock<
+    FxHashMap<&'static [u8], &'static WhitespaceMinification>,
+> = LazyLock::new(|| {
+    let mut m = FxHashMap::<&'static [u8], &'static WhitespaceMinification>::default();
+    // Token elements, whose text is trimmed and collapsed:
+    // https://w3c.github.io/mathml-core/#token-elements.
+    m.insert(b"mi", CONTENT);
+    m.insert(b"mn", CONTENT);
+    m.insert(b"mo", CONTENT);
+    m.insert(b"ms", CONTENT);
+    m.insert(b"mtext", CONTENT);
+
+    // Annotations aren't rendered and their content may be in any format.
+    m.insert(b"annotation", WHITESPACE_SENSITIVE);
+    m.insert(b"annotation-xml", WHITESPACE_SENSITIVE);
+
+    m
+});
+
 pub fn get_whitespace_minification_for_tag(
     ns: Namespace,
     // Use empty slice if root.
@@ -294,6 +321,9 @@ pub fn get_whitespace_minification_for_tag(
                     .unwrap_or(&DEFAULT_HTML)
             }
         }
+        Namespace::MathMl => MATHML_TAG_WHITESPACE_MINIFICATION
+            .get(tag_name)
+            .unwrap_or(&DEFAULT_MATHML),
         Namespace::Svg => SVG_TAG_WHITESPACE_MINIFICATION
             .get(tag_name)
             .unwrap_or(&DEFAULT_SVG),
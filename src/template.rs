@@ -0,0 +1,70 @@
+use memchr::memmem;
+
+use crate::entity::decode::decode_entities;
+
+// Opening and closing delimiters of template tags, such as `{{` and `}}`. See
+// `Cfg::template_delimiters`.
+pub type TemplateDelimiters = [(Vec<u8>, Vec<u8>)];
+
+// Position of the first template tag in `src`.
+pub fn find_template(delims: &TemplateDelimiters, src: &[u8]) -> Option<usize> {
+    delims
+        .iter()
+        .filter(|(open, _)| !open.is_empty())
+        .filter_map(|(open, _)| memmem::find(src, open))
+        .min()
+}
+
+pub fn contains_template(delims: &TemplateDelimiters, src: &[u8]) -> bool {
+    find_template(delims, src).is_some()
+}
+
+// Length of the template tag at the start of `src`, including its delimiters. An unclosed template
+// tag runs to the end of `src`.
+pub fn template_len(delims: &TemplateDelimiters, src: &[u8]) -> Option<usize> {
+    let (open, close) = delims
+        .iter()
+        .filter(|(open, _)| !open.is_empty() && src.starts_with(open))
+        // Prefer the longest match, e.g. `{{{` over `{{`.
+        .max_by_key(|(open, _)| open.len())?;
+    Some(match memmem::find(&src[open.len()..], close) {
+        Some(end) => open.len() + end + close.len(),
+        None => src.len(),
+    })
+}
+
+// Splits `src` into runs of text and template tags, in order. The bool is true for template tags.
+pub fn split_templates<'s>(
+    delims: &TemplateDelimiters,
+    mut src: &'s [u8],
+) -> impl Iterator<Item = (bool, &'s [u8])> {
+    std::iter::from_fn(move || {
+        if src.is_empty() {
+            return None;
+        };
+        let len = match template_len(delims, src) {
+            Some(len) => return Some((true, take(&mut src, len))),
+            None => find_template(delims, src).unwrap_or(src.len()),
+        };
+        Some((false, take(&mut src, len)))
+    })
+}
+
+// Decodes the entities in text around its template tags, keeping the template tags as they are.
+pub fn decode_templated(delims: &TemplateDelimiters, src: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(src.len());
+    for (is_template, s) in split_templates(delims, src) {
+        if is_template {
+            out.extend_from_slice(s);
+        } else {
+            out.extend_from_slice(&decode_entities(s, false));
+        };
+    }
+    out
+}
+
+fn take<'s>(src: &mut &'s [u8], len: usize) -> &'s [u8] {
+    let (head, tail) = src.split_at(len);
+    *src = tail;
+    head
+}
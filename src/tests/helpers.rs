@@ -82,13 +82,40 @@ pub fn create_common_test_data() -> HashMap<&'static [u8], &'static [u8]> {
     t.insert(b"<svg><path d='a/' /></svg>", b"<svg><path d=a/ /></svg>");
     t.insert(b"<svg><path d='a/'/></svg>", b"<svg><path d=a/ /></svg>");
 
+    // self closing mathml tags and whitespace
+    t.insert(
+        b"<math> <mrow> <mi/> <mo> + </mo> <mn> 1 </mn> </mrow> </math>",
+        b"<math><mrow><mi/><mo>+</mo><mn>1</mn></mrow></math>",
+    );
+    t.insert(
+        b"<p>a <math display=INLINE><mtext> b  <b> c </b> </mtext></math> d",
+        b"<p>a <math><mtext>b <b> c </b></mtext></math> d",
+    );
+    t.insert(
+        b"<math><mtable><mtr><mtd columnspan=1 rowspan=2></mtd></mtr></mtable></math>",
+        b"<math><mtable><mtr><mtd rowspan=2></mtd></mtr></mtable></math>",
+    );
+    t.insert(
+        b"<math><semantics><mi>x</mi><annotation> a  b </annotation></semantics></math>",
+        b"<math><semantics><mi>x</mi><annotation> a  b </annotation></semantics></math>",
+    );
+    // `annotation-xml` content is HTML if its encoding is, so `<br/>` isn't self-closing there.
+    t.insert(
+        b"<math><annotation-xml encoding=\"TEXT/HTML\"><p>a</p><br/></annotation-xml></math>",
+        b"<math><annotation-xml encoding=TEXT/HTML><p>a</p><br></annotation-xml></math>",
+    );
+    t.insert(
+        b"<math><annotation-xml encoding=application/mathml+xml><mi/><br/></annotation-xml></math>",
+        b"<math><annotation-xml encoding=application/mathml+xml><mi/><br/></annotation-xml></math>",
+    );
+
     // parsing with omitted tags
     t.insert(b"<ul><li>1<li>2<li>3</ul>", b"<ul><li>1<li>2<li>3</ul>");
     t.insert(b"<rt>", b"<rt>");
     t.insert(b"<rt><rp>1</rp><div></div>", b"<rt><rp>1</rp><div></div>");
     t.insert(b"<div><rt></div>", b"<div><rt></div>");
-    t.insert(b"<html><head><body>", b"<html><head><body>");
-    t.insert(b"<html><head><body>", b"<html><head><body>");
+    t.insert(b"<html><head><body>", b"<html><head>");
+    t.insert(b"<html><head><body>", b"<html><head>");
     // Tag names should be case insensitive.
     t.insert(b"<rt>", b"<rt>");
 
@@ -113,7 +140,7 @@ pub fn create_common_test_data() -> HashMap<&'static [u8], &'static [u8]> {
             </body>
         </html>
     "#,
-        b"<html><head><body>",
+        b"<html><head>",
     );
     // Tag names should be case insensitive.
     t.insert(b"<RT></rt>", b"<rt>");
@@ -397,7 +424,10 @@ pub fn create_common_test_data() -> HashMap<&'static [u8], &'static [u8]> {
             b"<pre>a <!-- akd--sj\n <!-- \t\0f--ajk--df->lafj -->  b</pre>",
             b"<pre>a   b</pre>",
         );
-        t.insert(b"&a<!-- akd--sj\n <!-- \t\0f--ajk--df->lafj -->mp", b"&amp");
+        t.insert(
+            b"&a<!-- akd--sj\n <!-- \t\0f--ajk--df->lafj -->mp",
+            b"&amp;amp",
+        );
         t.insert(
             b"<script><!-- akd--sj\n <!-- \t\0f--ajk--df->lafj --></script>",
             b"<script></script>",
@@ -489,3 +519,24 @@ pub fn create_common_js_test_data() -> HashMap<&'static [u8], &'static [u8]> {
 
     t
 }
+
+#[cfg(feature = "js")]
+pub fn create_common_js_module_test_data() -> HashMap<&'static [u8], &'static [u8]> {
+    let mut t = HashMap::<&'static [u8], &'static [u8]>::new();
+
+    // js module minification
+    t.insert(
+        b"<script type=module>import { a } from './a.js';\n  a( 1 );</script>",
+        b"<script type=module>import{a}from\"./a.js\";a(1);</script>",
+    );
+    t.insert(
+        b"<script type=module>export const b = 1;</script>",
+        b"<script type=module>export const b=1;</script>",
+    );
+    t.insert(
+        b"<script type=module>const r = await fetch( '/x' );\nconsole.log(r);</script>",
+        b"<script type=module>const r=await fetch(`/x`);console.log(r);</script>",
+    );
+
+    t
+}
@@ -1,25 +1,40 @@
 mod helpers;
 
-use std::str::from_utf8;
+use std::{io::Write, str::from_utf8, sync::Arc};
 
+#[cfg(feature = "css")]
+use crate::cfg::CssOptions;
 #[cfg(feature = "css")]
 use crate::tests::helpers::create_common_css_test_data;
 #[cfg(feature = "js")]
-use crate::tests::helpers::create_common_js_test_data;
-use crate::{cfg::Cfg, minify, tests::helpers::create_common_test_data};
+use crate::{
+    Report,
+    cfg::JsOptions,
+    tests::helpers::{create_common_js_module_test_data, create_common_js_test_data},
+};
+use crate::{
+    WarningKind,
+    cfg::{AttrOrder, Cfg, CssTargets},
+    minify, minify_to_writer, minify_with_report, minify_with_source_map, semantically_equal,
+    tests::helpers::create_common_test_data,
+    try_minify,
+};
 
+#[allow(clippy::needless_borrow)]
 pub fn eval_with_cfg(src: &'static [u8], expected: &'static [u8], cfg: Cfg) {
     let min = minify(&src, Some(cfg));
     assert_eq!(from_utf8(&min).unwrap(), from_utf8(expected).unwrap(),);
 }
 
 #[cfg(feature = "js")]
+#[allow(clippy::unused_unit)]
 pub fn eval_with_js_min(src: &'static [u8], expected: &'static [u8]) -> () {
     let cfg = Cfg::new();
     eval_with_cfg(src, expected, cfg);
 }
 
 #[cfg(feature = "css")]
+#[allow(clippy::unused_unit)]
 pub fn eval_with_css_min(src: &'static [u8], expected: &'static [u8]) -> () {
     let cfg = Cfg::new();
     eval_with_cfg(src, expected, cfg);
@@ -33,10 +48,20 @@ pub fn eval(src: &'static [u8], expected: &'static [u8]) {
 }
 
 // NOTE: This is different to `eval` as that enables `keep_html_and_head_opening_tags`.
+#[allow(clippy::unused_unit)]
 fn eval_without_keep_html_head(src: &'static [u8], expected: &'static [u8]) -> () {
     eval_with_cfg(src, expected, Cfg::new());
 }
 
+// NOTE: This is different to `eval` as it also enables `keep_body_colgroup_and_tbody_opening_tags`, for
+// tests of other minifications whose expected output has those tags.
+fn eval_keep_body(src: &'static [u8], expected: &'static [u8]) {
+    let mut cfg = Cfg::new();
+    cfg.keep_html_and_head_opening_tags = true;
+    cfg.keep_body_colgroup_and_tbody_opening_tags = true;
+    eval_with_cfg(src, expected, cfg);
+}
+
 #[test]
 fn test_common() {
     for (a, b) in create_common_test_data() {
@@ -50,9 +75,14 @@ fn test_common() {
     for (a, b) in create_common_js_test_data() {
         eval_with_js_min(a, b);
     }
+    #[cfg(feature = "js")]
+    for (a, b) in create_common_js_module_test_data() {
+        eval_with_js_min(a, b);
+    }
 }
 
 #[test]
+#[allow(clippy::field_reassign_with_default)]
 fn test_keep_ssi_comments() {
     eval(b"<!--#include >", b"");
     let mut cfg = Cfg::default();
@@ -61,6 +91,143 @@ fn test_keep_ssi_comments() {
 }
 
 #[test]
+fn test_text_around_removed_comments() {
+    // The text is joined, so it's encoded and its whitespace collapsed as one.
+    eval(b"&a<!-- b -->mp", b"&amp;amp");
+    eval(b"<div>a <!-- b --> c</div>", b"<div>a c</div>");
+    let cfg = Cfg {
+        keep_comments: true,
+        ..Cfg::default()
+    };
+    eval_with_cfg(b"&a<!-- b -->mp", b"&a<!-- b -->mp", cfg);
+}
+
+#[test]
+fn test_keep_comments_if() {
+    let cfg = Cfg {
+        keep_comments_if: Some(Arc::new(|c: &[u8]| {
+            c.starts_with(b"!") || c == b"[" || c == b"]" || c == b"$" || c == b" htmlmin:ignore "
+        })),
+        ..Cfg::default()
+    };
+    eval_with_cfg(
+        b"<!--! License --><!-- a --><div><!--[--><!--$-->b<!--/$--><!--]--></div><!-- htmlmin:ignore -->",
+        b"<!--! License --><div><!--[--><!--$-->b<!--]--></div><!-- htmlmin:ignore -->",
+        cfg,
+    );
+}
+
+#[test]
+fn test_minify_off_regions() {
+    eval(
+        b"<div> a <!-- minify:off --> <b  class=\"x\" >&amp; \n</b> <!-- MINIFY:ON --> c </div>",
+        b"<div>a  <b  class=\"x\" >&amp; \n</b>  c</div>",
+    );
+    eval(
+        b"<p> a </p><!--minify:off--><p> b </p><!-- minify:on --><p> c </p>",
+        b"<p>a</p><p> b </p><p>c",
+    );
+    // Without a `minify:on` comment, the rest of the document is passed through.
+    eval(
+        b"<p> a <!-- minify:off --><p> b </p>\n<!-- c -->",
+        b"<p>a <p> b </p>\n<!-- c -->",
+    );
+    let cfg = Cfg {
+        keep_comments: true,
+        ..Cfg::default()
+    };
+    eval_with_cfg(
+        b"<i> a </i><!-- minify:off --> <i> b </i> <!-- minify:on -->",
+        b"<i> a </i><!-- minify:off --> <i> b </i> <!-- minify:on -->",
+        cfg,
+    );
+}
+
+#[test]
+fn test_template_syntax() {
+    let cfg = Cfg {
+        template_delimiters: Cfg::common_template_delimiters(),
+        ..Cfg::default()
+    };
+    let eval_templated =
+        |src: &'static [u8], expected: &'static [u8]| eval_with_cfg(src, expected, cfg.clone());
+    eval_templated(
+        b"<p>  Hello,  {{  user.name  |  default(\"a  <b>\") }}  !  {# a  comment #}</p>",
+        b"<p>Hello, {{  user.name  |  default(\"a  <b>\") }} ! {# a  comment #}",
+    );
+    eval_templated(
+        b"<ul>\n  {% for x in xs %}\n  <li> {{ x }} </li>\n  {% endfor %}\n</ul>",
+        b"<ul>{% for x in xs %}<li>{{ x }}</li>{% endfor %}</ul>",
+    );
+    eval_templated(
+        b"<a href=\"{{ url_for(\"a\", b='c') }}\" title='{{ t }} &amp; \"x\"' class=\"  a  {{ c }}  \">x</a>",
+        b"<a class=\"  a  {{ c }}  \" href=\"{{ url_for(\"a\", b='c') }}\" title='{{ t }} & \"x\"'>x</a>",
+    );
+    eval_templated(
+        b"<input id=a {% if x %}checked{% endif %} {%if y%}disabled=\"{{ z }}\"{%endif%} />",
+        b"<input id=a {% if x %}checked{% endif %} {%if y%}disabled=\"{{ z }}\" {%endif%}>",
+    );
+    // Attributes after a template tag are still parsed and minified.
+    eval_templated(
+        b"<input {% if x %} checked {% endif %} TYPE=TEXT class=' a ' DATA-{{ K }}=\" v \">",
+        b"<input {% if x %} checked {% endif %} class=a data-{{ K }}=\" v \">",
+    );
+    // Entities in template tags in `<title>` and `<textarea>` aren't decoded or encoded.
+    eval_templated(
+        b"<title>{{ a &amp;&amp; \"&lt;\" }} &amp; b</title><textarea>{% if x %}&lt;&#97;{% endif %}</textarea>",
+        b"<title>{{ a &amp;&amp; \"&lt;\" }} & b</title><textarea>{% if x %}<a{% endif %}</textarea>",
+    );
+    // Repeated attributes can't all be kept, so they're left as they are.
+    eval_templated(
+        b"<a  href=' b '  {% if x %} class=c {% else %} class='d' {% endif %}>e</a>",
+        b"<a href=\" b \" {% if x %} class=c {% else %} class='d' {% endif %}>e</a>",
+    );
+    eval_templated(
+        b"<img alt={{x}} src={{ y }}><script>let a = {{ data | tojson }};</script>",
+        b"<img src=\"{{ y }}\" alt={{x}}><script>let a = {{ data | tojson }};</script>",
+    );
+    eval_templated(
+        b"<p><%= link_to 'a', b %>  c</p>",
+        b"<p><%= link_to 'a', b %> c",
+    );
+    // Without template delimiters, they're treated as text.
+    eval(b"<p>{{  a  }}</p>", b"<p>{{ a }}");
+}
+
+#[test]
+fn test_keep_conditional_comments() {
+    let src: &[u8] = b"<!-- a --><!--[if mso]> <table> <tr> <td> <![endif]--><p>b</p><!--[if mso]> </td> </tr> </table> <![endif]--><!--[if !mso]><!--> <i> c </i> <!--<![endif]-->";
+    eval(src, b"<p>b</p><i> c </i>");
+    let cfg = Cfg {
+        keep_conditional_comments: true,
+        ..Cfg::default()
+    };
+    eval_with_cfg(
+        src,
+        b"<!--[if mso]> <table> <tr> <td> <![endif]--><p>b</p><!--[if mso]> </td> </tr> </table> <![endif]--><!--[if !mso]><!--><i> c </i><!--<![endif]-->",
+        cfg.clone(),
+    );
+    // The second comment only has closing tags, so it's left as is.
+    eval_with_cfg(
+        src,
+        b"<!--[if mso]><table><tr><td><![endif]--><p>b</p><!--[if mso]> </td> </tr> </table> <![endif]--><!--[if !mso]><!--><i> c </i><!--<![endif]-->",
+        Cfg {
+            minify_conditional_comments: true,
+            ..cfg.clone()
+        },
+    );
+    eval_with_cfg(
+        b"<!--[if IE]><p>a --&gt; b</p><![endif]-->",
+        b"<!--[if IE]><p>a --&gt; b</p><![endif]-->",
+        Cfg {
+            minify_conditional_comments: true,
+            ..cfg
+        },
+    );
+}
+
+#[test]
+#[allow(clippy::field_reassign_with_default)]
 fn test_keep_input_type_text_attr() {
     eval(b"<input type=\"text\">", b"<input>");
     let mut cfg = Cfg::default();
@@ -70,23 +237,23 @@ fn test_keep_input_type_text_attr() {
 
 #[test]
 fn test_removal_of_empty_closing_tag() {
-    eval(b"<body><p>1</><p>2</body>", b"<body><p>1<p>2");
+    eval_keep_body(b"<body><p>1</><p>2</body>", b"<body><p>1<p>2");
 }
 
 #[test]
 fn test_parsing_extra_head_tag() {
     // Extra `<head>` in `<label>` should be dropped, so whitespace around `<head>` should be joined and therefore trimmed due to `<label>` whitespace rules.
-    eval(
+    eval_keep_body(
         b"<html><head><meta><head><link><head><body><label>  <pre> </pre> <head>  </label>",
         b"<html><head><meta><link><body><label><pre> </pre></label>",
     );
     // Same as above except it's a `</head>`, which should get reinterpreted as a `<head>`.
-    eval(
+    eval_keep_body(
         b"<html><head><meta><head><link><head><body><label>  <pre> </pre> </head>  </label>",
         b"<html><head><meta><link><body><label><pre> </pre></label>",
     );
     // `<head>` gets implicitly closed by `<body>`, so any following `</head>` should be ignored. (They should be anyway, since `</head>` would not be a valid closing tag.)
-    eval(
+    eval_keep_body(
         b"<html><head><body><label> </head> </label>",
         b"<html><head><body><label></label>",
     );
@@ -97,17 +264,105 @@ fn test_removal_of_html_and_head_opening_tags() {
     // Even though `<head>` is dropped, it's still parsed, so its content is still subject to `<head>` whitespace minification rules.
     eval_without_keep_html_head(
         b"<!DOCTYPE html><html><head>  <meta> <body>",
-        b"<!doctype html><meta><body>",
+        b"<!doctype html><meta>",
     );
     // The tag should not be dropped if it has attributes.
     eval_without_keep_html_head(
         b"<!DOCTYPE html><html lang=en><head>  <meta> <body>",
-        b"<!doctype html><html lang=en><meta><body>",
+        b"<!doctype html><html lang=en><meta>",
     );
     // The tag should be dropped if it has no attributes after minification.
     eval_without_keep_html_head(
         b"<!DOCTYPE html><html style='  '><head>  <meta> <body>",
-        b"<!doctype html><meta><body>",
+        b"<!doctype html><meta>",
+    );
+}
+
+#[test]
+fn test_removal_of_optional_opening_tags() {
+    let cfg = Cfg::default;
+    eval_with_cfg(
+        b"<!DOCTYPE html><html><head><title>a</title></head><body> <p>b</p></body></html>",
+        b"<!doctype html><title>a</title><p>b",
+        cfg(),
+    );
+    // `<html>` must be kept if it starts with a comment, and `<head>` if it doesn't start with an element.
+    eval_with_cfg(
+        b"<html><!-- a --><head><!-- b --><title>c</title>",
+        b"<html><!-- a --><head><!-- b --><title>c</title>",
+        Cfg {
+            keep_comments: true,
+            ..cfg()
+        },
+    );
+    // `<body>` must be kept if it starts with a comment or an element that would otherwise be placed
+    // in `<head>`.
+    eval_with_cfg(b"<head></head><body><meta></body>", b"<body><meta>", cfg());
+    eval_with_cfg(
+        b"<body><!-- a -->b",
+        b"<body><!-- a -->b",
+        Cfg {
+            keep_comments: true,
+            ..cfg()
+        },
+    );
+    // Elements that would be put in `<head>` if `<body>` were omitted.
+    for tag in [
+        "base", "basefont", "bgsound", "link", "meta", "noframes", "noscript", "script", "style",
+        "template", "title",
+    ] {
+        let src = format!("<html><head></head><body><{tag}></{tag}><p>a");
+        let min = minify(src.as_bytes(), Some(cfg()));
+        assert!(
+            min.starts_with(b"<body>"),
+            "{src}: {}",
+            from_utf8(&min).unwrap()
+        );
+        semantically_equal(src.as_bytes(), &min).unwrap();
+    }
+    eval_with_cfg(b"<body class=a>b", b"<body class=a>b", cfg());
+    eval_with_cfg(b"<body>b", b"b", cfg());
+    // The whitespace around a removed comment is trimmed as one.
+    eval_with_cfg(b"<body>  <!-- a -->  b", b"b", cfg());
+    // `<colgroup>` and `<tbody>` can only be omitted if they start with `<col>` and `<tr>`, and the
+    // previous sibling isn't the same kind of element with an omitted closing tag.
+    eval_with_cfg(
+        b"<table><colgroup><col></colgroup><colgroup span=2></colgroup><tbody><tr><td>1</td></tr></tbody><tbody><tr><td>2</td></tr></tbody></table>",
+        b"<table><col></colgroup><colgroup span=2></colgroup><tr><td>1<tbody><tr><td>2</table>",
+        cfg(),
+    );
+    // A `</colgroup>` closes the `<colgroup>` that's implied around `<col>` elements, so it's kept.
+    eval_with_cfg(
+        b"<table><col> <col></colgroup><col></table>",
+        b"<table><col><col></colgroup><col></table>",
+        cfg(),
+    );
+    eval_with_cfg(
+        b"<table><thead><tr><th>1</th></tr></thead><tbody><tr><td>2</td></tr></tbody></table>",
+        b"<table><thead><tr><th>1<tbody><tr><td>2</table>",
+        cfg(),
+    );
+    eval_with_cfg(
+        b"<table><thead><tr><th>1</th></tr></thead><tbody><tr><td>2</td></tr></tbody></table>",
+        b"<table><thead><tr><th>1</th></tr></thead><tr><td>2</td></tr></tbody></table>",
+        Cfg {
+            keep_closing_tags: true,
+            ..cfg()
+        },
+    );
+    eval_with_cfg(
+        b"<table><tbody></tbody></table>",
+        b"<table><tbody></table>",
+        cfg(),
+    );
+    eval_with_cfg(
+        b"<table><tbody><tr></tr></tbody></table>",
+        b"<table><tbody><tr></tr></tbody></table>",
+        Cfg {
+            keep_body_colgroup_and_tbody_opening_tags: true,
+            keep_closing_tags: true,
+            ..cfg()
+        },
     );
 }
 
@@ -118,7 +373,7 @@ fn test_unmatched_closing_tag() {
     eval(b"<div>Hello</p>Goodbye", b"<div>HelloGoodbye");
     eval(b"<ul><li>a</p>", b"<ul><li>a");
     eval(b"<ul><li><rt>a</p>", b"<ul><li><rt>a");
-    eval(
+    eval_keep_body(
         b"<html><head><body><ul><li><rt>a</p>",
         b"<html><head><body><ul><li><rt>a",
     );
@@ -166,3 +421,560 @@ fn test_doctype_minification() {
         br#"<!doctype html>"#,
     )
 }
+
+#[test]
+fn test_minify_with_report() {
+    let report = minify_with_report(b"<html><body><p>a</></br><body><!x", Some(Cfg::new()));
+    assert_eq!(
+        report
+            .warnings
+            .iter()
+            .map(|w| (w.kind, w.offset))
+            .collect::<Vec<_>>(),
+        vec![
+            (WarningKind::MalformedClosingTag, 16),
+            (WarningKind::MismatchedClosingTag, 19),
+            (WarningKind::IgnoredDuplicateTag, 24),
+            (WarningKind::UnclosedBang, 30),
+        ]
+    );
+    assert_eq!(
+        minify_with_report(b"a<?x", None).warnings[0].kind,
+        WarningKind::UnclosedInstruction
+    );
+    assert_eq!(
+        minify_with_report(b"a<!--x", None).warnings[0].kind,
+        WarningKind::UnclosedComment
+    );
+    assert!(try_minify(b"<p>a</p>", None).is_ok());
+    assert_eq!(try_minify(b"<p>a</div>", None).unwrap_err().len(), 1);
+}
+
+#[test]
+#[cfg(feature = "css")]
+fn test_minify_with_report_invalid_css() {
+    let report = minify_with_report(
+        b"<style>\na { color: red }\n}</style><p style='color: red; {'>",
+        None,
+    );
+    let kinds = report
+        .warnings
+        .iter()
+        .map(|w| (w.kind, w.offset))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![(WarningKind::InvalidCss, 26), (WarningKind::InvalidCss, 56)]
+    );
+}
+
+#[test]
+#[cfg(feature = "js")]
+fn test_minify_with_report_invalid_js() {
+    let report = minify_with_report(b"<script>let a = ;</script>", None);
+    assert_eq!(report.output, b"<script>let a = ;</script>".to_vec());
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].kind, WarningKind::InvalidJs);
+    assert_eq!(report.warnings[0].offset, 16);
+}
+
+#[test]
+#[cfg(feature = "css")]
+fn test_invalid_utf8_css() {
+    let src = b"<style>a { content: '\xff' }</style><p style=\"content: '\xff'\">";
+    let report = minify_with_report(src, None);
+    assert_eq!(report.output, src.to_vec());
+    assert_eq!(
+        report
+            .warnings
+            .iter()
+            .map(|w| (w.kind, w.offset))
+            .collect::<Vec<_>>(),
+        vec![
+            (WarningKind::InvalidUtf8, 21),
+            (WarningKind::InvalidUtf8, 53)
+        ]
+    );
+
+    let cfg = Cfg {
+        decode_invalid_utf8_lossily: true,
+        ..Cfg::default()
+    };
+    let report = minify_with_report(src, Some(cfg));
+    assert_eq!(
+        from_utf8(&report.output).unwrap(),
+        "<style>a{content:\"\u{fffd}\"}</style><p style='content:\"\u{fffd}\"'>"
+    );
+    assert_eq!(report.warnings.len(), 2);
+}
+
+#[test]
+#[cfg(feature = "js")]
+fn test_invalid_utf8_js() {
+    let src = b"<script>let a = '\xff';</script>";
+    let report = minify_with_report(src, None);
+    assert_eq!(report.output, b"<script>let a = '\xff';</script>".to_vec());
+    assert_eq!(report.warnings[0].kind, WarningKind::InvalidUtf8);
+    assert_eq!(report.warnings[0].offset, 17);
+
+    let cfg = Cfg {
+        decode_invalid_utf8_lossily: true,
+        ..Cfg::default()
+    };
+    assert_eq!(
+        from_utf8(&minify(src, Some(cfg))).unwrap(),
+        "<script>let a=`\u{fffd}`;</script>"
+    );
+}
+
+#[test]
+#[cfg(feature = "js")]
+fn test_js_attr_minification() {
+    eval_with_js_min(
+        b"<button onclick=\"  if (confirm( 'Sure?' )) { submit(); }  return false;  \">a</button>",
+        b"<button onclick=\"return confirm(`Sure?`)&&submit(),!1;\">a</button>",
+    );
+    eval_with_js_min(
+        b"<a href=\"javascript: void  ( 0 )\" onmouseover=\"f()\">a</a>",
+        b"<a href=\"javascript:void 0;\" onmouseover=f()>a</a>",
+    );
+    // Percent-encoded URLs and invalid code are left as is.
+    eval_with_js_min(
+        b"<a href=\"javascript:alert( 'a%20b' )\" onclick=\"let a = ;\">a</a>",
+        b"<a href=\"javascript:alert( 'a%20b' )\" onclick=\"let a = ;\">a</a>",
+    );
+    // Attributes that only look like event handlers, such as those of custom elements, aren't code.
+    let src: &[u8] = b"<x-a onsale=\"  let   a = 1 ;  \"></x-a><my-card onsale=\"Summer sale: 50% off!\" onboarding=\"step one\"></my-card>";
+    let Report { output, warnings } = minify_with_report(src, None);
+    assert_eq!(
+        from_utf8(&output).unwrap(),
+        "<x-a onsale=\"  let   a = 1 ;  \"></x-a><my-card onboarding=\"step one\" onsale=\"Summer sale: 50% off!\"></my-card>"
+    );
+    assert_eq!(warnings, vec![]);
+}
+
+#[test]
+#[cfg(feature = "js")]
+fn test_js_options() {
+    let src: &[u8] =
+        b"<script>function hello(name) { debugger; console.log(name); } hello(1);</script>";
+    eval_with_cfg(
+        src,
+        b"<script>function hello(e){console.log(e)}hello(1);</script>",
+        Cfg::new(),
+    );
+    eval_with_cfg(
+        src,
+        b"<script>function hello(name){console.log(name)}hello(1);</script>",
+        Cfg {
+            js: JsOptions {
+                mangle: false,
+                ..JsOptions::default()
+            },
+            ..Cfg::default()
+        },
+    );
+    eval_with_cfg(
+        src,
+        b"<script>function e(e){}e(1);</script>",
+        Cfg {
+            js: JsOptions {
+                drop_console: true,
+                mangle_top_level: true,
+                ..JsOptions::default()
+            },
+            ..Cfg::default()
+        },
+    );
+    eval_with_cfg(
+        src,
+        b"<script>function hello(e){debugger;console.log(e)}hello(1);</script>",
+        Cfg {
+            js: JsOptions {
+                drop_debugger: false,
+                ..JsOptions::default()
+            },
+            ..Cfg::default()
+        },
+    );
+}
+
+#[test]
+fn test_css_targets_from_queries() {
+    let targets =
+        CssTargets::from_queries(["chrome 95", "Safari >= 14.1", "chrome 90", "ios 15.4.1"])
+            .unwrap();
+    assert_eq!(
+        targets,
+        CssTargets {
+            chrome: Some((90, 0)),
+            ios_saf: Some((15, 4)),
+            safari: Some((14, 1)),
+            ..CssTargets::default()
+        }
+    );
+    assert!(CssTargets::from_queries(["netscape 4"]).is_err());
+    assert!(CssTargets::from_queries(["chrome"]).is_err());
+    assert!(CssTargets::from_queries(["chrome x"]).is_err());
+}
+
+#[test]
+#[cfg(feature = "css")]
+fn test_css_options() {
+    let cfg = Cfg {
+        css: CssOptions {
+            targets: CssTargets::from_queries(["chrome 60", "safari 10"]).unwrap(),
+            ..CssOptions::default()
+        },
+        ..Cfg::default()
+    };
+    eval_with_cfg(
+        b"<style>a { &:hover { color: red } }</style><p style='user-select: none'>",
+        b"<style>a:hover{color:red}</style><p style=-webkit-user-select:none;user-select:none>",
+        cfg,
+    );
+
+    let src: &[u8] = b"<style>a { color: red } b { color: red !imp }</style>";
+    let report = minify_with_report(src, None);
+    assert_eq!(report.output, src.to_vec());
+    let cfg = Cfg {
+        css: CssOptions {
+            error_recovery: true,
+            ..CssOptions::default()
+        },
+        ..Cfg::default()
+    };
+    let report = minify_with_report(src, Some(cfg));
+    assert_eq!(
+        from_utf8(&report.output).unwrap(),
+        "<style>a{color:red}</style>"
+    );
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].kind, WarningKind::InvalidCss);
+    assert!(
+        report.warnings[0]
+            .message
+            .starts_with("dropped invalid CSS: ")
+    );
+
+    // Unknown at-rules are kept without warnings unless they should be removed.
+    let src: &[u8] = b"<style>@tailwind base; @foo bar { a { b: c } } a { color: red }</style>";
+    let report = minify_with_report(src, None);
+    assert_eq!(
+        from_utf8(&report.output).unwrap(),
+        "<style>@tailwind base;@foo bar{a { b: c }}a{color:red}</style>"
+    );
+    assert_eq!(report.warnings, vec![]);
+    let cfg = Cfg {
+        css: CssOptions {
+            preserve_unknown_at_rules: false,
+            ..CssOptions::default()
+        },
+        ..Cfg::default()
+    };
+    let report = minify_with_report(src, Some(cfg));
+    assert_eq!(
+        from_utf8(&report.output).unwrap(),
+        "<style>a{color:red}</style>"
+    );
+    assert_eq!(report.warnings, vec![]);
+}
+
+#[test]
+#[cfg(feature = "css")]
+fn test_svg_css_minification() {
+    eval_with_css_min(
+        b"<svg><style><![CDATA[ .a > .b { fill : #ff0000 } ]]> .c::after { content: '&amp;' }</style><rect style=' fill : #FFFFFF ; ' fill=\"#ff0000\" stroke=' currentColor ' opacity=0.50 transform='rotate(45 1 1)'/></svg>",
+        b"<svg><style>.a>.b{fill:red}.c:after{content:\"&\"}</style><rect transform=\"rotate(45 1 1)\" fill=red opacity=.5 stroke=currentColor style=fill:#fff /></svg>",
+    );
+    // `<style>` with elements in it isn't CSS only.
+    eval_with_css_min(
+        b"<svg><style> a { }<title>x</title></style></svg>",
+        b"<svg><style>a { }<title>x</title></style></svg>",
+    );
+}
+
+#[test]
+fn test_minify_svg_path_data() {
+    let src: &[u8] = b"<svg viewBox=\"0, 0, 10.0, 10.0\"><path d=\"M 0.5,0.5 L 1.0 , -1.0 Z\"/><polygon points=\"0,0 0.5 , 1\"/><path d=\"M 1\"/></svg>";
+    eval(
+        src,
+        b"<svg viewbox=\"0, 0, 10.0, 10.0\"><path d=\"M 0.5,0.5 L 1.0 , -1.0 Z\"/><polygon points=\"0,0 0.5 , 1\"/><path d=\"M 1\"/></svg>",
+    );
+    let cfg = Cfg {
+        minify_svg_path_data: true,
+        ..Cfg::default()
+    };
+    eval_with_cfg(
+        src,
+        b"<svg viewbox=\"0 0 10 10\"><path d=M.5.5L1-1Z /><polygon points=\"0 0 .5 1\"/><path d=\"M 1\"/></svg>",
+        cfg,
+    );
+}
+
+#[test]
+fn test_json_script_minification() {
+    eval(
+        b"<script type=application/ld+json>\n  { \"@type\" : \"Thing\", \"a\" : [ 1, -2.5e+3, true, null ] }\n</script>",
+        b"<script type=application/ld+json>{\"@type\":\"Thing\",\"a\":[1,-2.5e+3,true,null]}</script>",
+    );
+    eval(
+        b"<script type=importmap> { \"imports\" : { \"a\" : \"./a.js\" } } </script>",
+        b"<script type=importmap>{\"imports\":{\"a\":\"./a.js\"}}</script>",
+    );
+    eval(
+        b"<script type=\"application/json\">[ \"<\\/script>\", \"<!-- a\", \"\\u0041 b\" ]</script>",
+        b"<script type=application/json>[\"<\\/script>\",\"\\u003c!-- a\",\"\\u0041 b\"]</script>",
+    );
+
+    let report = minify_with_report(
+        b"<script type=application/json>{ \"a\": 1, }</script>",
+        None,
+    );
+    assert_eq!(
+        from_utf8(&report.output).unwrap(),
+        "<script type=application/json>{ \"a\": 1, }</script>"
+    );
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].kind, WarningKind::InvalidJson);
+    assert_eq!(report.warnings[0].offset, 40);
+
+    // Deep nesting doesn't overflow the stack.
+    let depth = 200_000;
+    let src = [
+        b"<script type=application/json>".as_slice(),
+        &b"[ ".repeat(depth),
+        &b"]".repeat(depth),
+        b"</script>",
+    ]
+    .concat();
+    let report = minify_with_report(&src, None);
+    assert_eq!(report.warnings, vec![]);
+    assert_eq!(
+        report.output,
+        [
+            b"<script type=application/json>".as_slice(),
+            &b"[".repeat(depth),
+            &b"]".repeat(depth),
+            b"</script>",
+        ]
+        .concat()
+    );
+
+    // Only JSON's whitespace is removed, and only whitespace is fine.
+    let report = minify_with_report(b"<script type=application/json>\x0c1</script>", None);
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].kind, WarningKind::InvalidJson);
+    let report = minify_with_report(b"<script type=application/json> \n </script>", None);
+    assert_eq!(report.warnings, vec![]);
+    assert_eq!(
+        from_utf8(&report.output).unwrap(),
+        "<script type=application/json></script>"
+    );
+}
+
+#[test]
+fn test_minify_to_writer() {
+    // Records the size of each write.
+    struct Chunks(Vec<usize>, Vec<u8>);
+
+    impl Write for Chunks {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.push(buf.len());
+            self.1.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let src = b"<div>  <p>  Hello,   world!  </p>  </div>\n".repeat(10_000);
+    let mut out = Chunks(Vec::new(), Vec::new());
+    minify_to_writer(&src, None, &mut out).unwrap();
+    assert_eq!(out.1, minify(&src, None));
+    assert!(out.0.len() > 1);
+
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let err = minify_to_writer(&src, None, &mut Broken).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn test_attr_order() {
+    let src = b"<div id=x title=\"a b\" data-z hidden class=\"c d\"></div>";
+    for (attr_order, expected) in [
+        (
+            AttrOrder::QuotedFirst,
+            "<div class=\"c d\" title=\"a b\" data-z hidden id=x></div>",
+        ),
+        (
+            AttrOrder::Name,
+            "<div class=\"c d\" data-z hidden id=x title=\"a b\"></div>",
+        ),
+        (
+            AttrOrder::Source,
+            "<div id=x title=\"a b\" data-z hidden class=\"c d\"></div>",
+        ),
+    ] {
+        let cfg = Cfg {
+            attr_order,
+            ..Cfg::default()
+        };
+        assert_eq!(from_utf8(&minify(src, Some(cfg))).unwrap(), expected);
+    }
+
+    let cfg = Cfg {
+        attr_order: AttrOrder::Source,
+        ..Cfg::default()
+    };
+    eval_with_cfg(
+        b"<svg><path d=a fill=\"b c\"/></svg>",
+        b"<svg><path d=a fill=\"b c\"/></svg>",
+        cfg.clone(),
+    );
+    eval_with_cfg(
+        b"<svg><path fill=\"b c\" d=a /></svg>",
+        b"<svg><path fill=\"b c\" d=a /></svg>",
+        cfg,
+    );
+}
+
+#[test]
+fn test_attr_order_frequency() {
+    let cfg = Cfg {
+        attr_order: AttrOrder::Frequency,
+        ..Cfg::default()
+    };
+    eval_with_cfg(
+        b"<p id=a class=\"x  y\">1</p><p title=t class=\"y z\" id=b>2</p><p class=\"z y x\">3</p>",
+        b"<p class=\"y x\" id=a>1<p class=\"y z\" id=b title=t>2<p class=\"y x z\">3",
+        cfg,
+    );
+}
+
+#[test]
+fn test_class_minification() {
+    let src = b"<div class=\" btn btn  primary btn lg primary \"></div>";
+    eval_with_cfg(
+        src,
+        b"<div class=\"btn btn primary btn lg primary\"></div>",
+        Cfg::default(),
+    );
+    let cfg = Cfg {
+        remove_duplicate_classes: true,
+        ..Cfg::default()
+    };
+    eval_with_cfg(src, b"<div class=\"btn primary lg\"></div>", cfg);
+    let cfg = Cfg {
+        remove_duplicate_classes: true,
+        sort_classes: true,
+        ..Cfg::default()
+    };
+    eval_with_cfg(src, b"<div class=\"btn lg primary\"></div>", cfg);
+    let cfg = Cfg {
+        remove_duplicate_classes: true,
+        ..Cfg::default()
+    };
+    eval_with_cfg(b"<div class=\"a  a\"></div>", b"<div class=a></div>", cfg);
+}
+
+#[test]
+fn test_source_map() {
+    let src = b"<div>\n  <p>Hello</p>\n  <!-- x -->\n  <p>World\n</div>";
+    let (out, map) = minify_with_source_map(src, None, "index.html");
+    assert_eq!(from_utf8(&out).unwrap(), "<div><p>Hello<p>World</div>");
+    // The removed comment doesn't claim the second `<p>`, and the closing tag maps to its own line.
+    assert_eq!(
+        map.mappings()
+            .iter()
+            .map(|m| (m.generated, m.original))
+            .collect::<Vec<_>>(),
+        vec![(0, 0), (5, 8), (8, 11), (13, 36), (16, 39), (21, 45)],
+    );
+    assert_eq!(map.original_offset(15), Some(36));
+    assert_eq!(
+        map.to_json(),
+        r#"{"version":3,"sources":["index.html"],"sourcesContent":["<div>\n  <p>Hello</p>\n  <!-- x -->\n  <p>World\n</div>"],"names":[],"mappings":"AAAA,KACE,GAAG,KAEH,GAAG,KACL"}"#,
+    );
+}
+
+#[cfg(feature = "js")]
+#[test]
+fn test_source_map_js() {
+    let src = "<p>\u{e9}<script>\n  let answer = '\u{e9}';\n  console.log(answer);\n</script>";
+    let (out, map) = minify_with_source_map(src.as_bytes(), None, "index.html");
+    let out = from_utf8(&out).unwrap();
+    // Each token of the script maps to where it was in the source, not just the start of the script.
+    let generated = out.find("console").unwrap();
+    assert_eq!(map.original_offset(generated), src.find("console"));
+}
+
+#[cfg(feature = "css")]
+#[test]
+fn test_source_map_css() {
+    let src = b"<style>\n  a { color: #ff0000; }\n  b { margin: 0px }\n</style>";
+    let (out, map) = minify_with_source_map(src, None, "index.html");
+    let out = from_utf8(&out).unwrap();
+    let src = from_utf8(src).unwrap();
+    let generated = out.find("b{").unwrap();
+    assert_eq!(map.original_offset(generated), src.find("b {"));
+}
+
+#[test]
+fn test_semantically_equal() {
+    // Every expected output of the common tests renders the same as its source.
+    for (src, expected) in create_common_test_data() {
+        // Script code is compared as text, so minifying it makes it differ.
+        if cfg!(feature = "js") && src.windows(7).any(|w| w == b"<script") {
+            continue;
+        };
+        if let Err(diff) = semantically_equal(src, expected) {
+            panic!("{}: {diff}", from_utf8(src).unwrap());
+        };
+    }
+    assert_eq!(
+        semantically_equal(
+            b"<table><tr><td>a</table>",
+            b"<table>\n<tbody><tr><td>a</td></tr></tbody></table>"
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        semantically_equal(
+            b"<table><colgroup><col></colgroup><colgroup><col></table>",
+            b"<table><col></colgroup><col>"
+        ),
+        Ok(())
+    );
+    let diff = semantically_equal(
+        b"<table><colgroup><col></colgroup><colgroup><col></table>",
+        b"<table><col><col>",
+    )
+    .unwrap_err();
+    assert_eq!(diff.path, "/html[1]/body[1]/table[1]/colgroup[1]/col[2]");
+    let diff = semantically_equal(b"<p title=a>x", b"<p title=b>x").unwrap_err();
+    assert_eq!(diff.path, "/html[1]/body[1]/p[1]/@title");
+    assert_eq!(
+        diff.to_string(),
+        r#"documents differ at /html[1]/body[1]/p[1]/@title: `title="a"` != `title="b"`"#
+    );
+    // Whitespace inside `<pre>` is significant.
+    let diff = semantically_equal(b"<pre>a  b</pre>", b"<pre>a b</pre>").unwrap_err();
+    assert_eq!(diff.path, "/html[1]/body[1]/pre[1]/text()[1]");
+    let diff = semantically_equal(b"<div><p>a</div>", b"<div><p>a</p><p></div>").unwrap_err();
+    assert_eq!(diff.path, "/html[1]/body[1]/div[1]/p[2]");
+    assert_eq!(diff.left, "");
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+const SRC: &str = "<div>\n  <p>Hello</p>\n</div>\n";
+const MIN: &str = "<div><p>Hello</div>";
+
+// A fresh directory for a test to write files in.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("simple-minify-html-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_simple-minify-html"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_output_dir() {
+    let dir = temp_dir("output-dir");
+    write(&dir.join("site/index.html"), SRC);
+    write(&dir.join("site/docs/a.htm"), SRC);
+    write(&dir.join("site/style.css"), "p {}");
+    let out = run(&dir, &["--output-dir", "dist", "site"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("Total: "));
+    assert_eq!(
+        fs::read_to_string(dir.join("dist/index.html")).unwrap(),
+        MIN
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("dist/docs/a.htm")).unwrap(),
+        MIN
+    );
+    assert!(!dir.join("dist/style.css").exists());
+    // The sources are left as they are.
+    assert_eq!(
+        fs::read_to_string(dir.join("site/index.html")).unwrap(),
+        SRC
+    );
+}
+
+#[test]
+fn test_output_dir_collision() {
+    let dir = temp_dir("collision");
+    write(&dir.join("a/index.html"), SRC);
+    write(&dir.join("b/index.html"), SRC);
+    let out = run(
+        &dir,
+        &["--output-dir", "dist", "a/index.html", "b/index.html"],
+    );
+    assert!(!out.status.success());
+    assert!(
+        String::from_utf8(out.stderr)
+            .unwrap()
+            .contains("would both be written to")
+    );
+    assert!(!dir.join("dist").exists());
+}
+
+#[test]
+fn test_in_place() {
+    let dir = temp_dir("in-place");
+    write(&dir.join("index.html"), SRC);
+    // The same file given twice, directly and through a glob, is only minified once.
+    let out = run(&dir, &["--in-place", "--quiet", "index.html", "*.html"]);
+    assert!(out.status.success());
+    assert_eq!(out.stdout, b"");
+    assert_eq!(fs::read_to_string(dir.join("index.html")).unwrap(), MIN);
+}